@@ -0,0 +1,243 @@
+//! ABI compatibility checks between the host and plugin libraries.
+//!
+//! `Vec<Box<dyn Plugin>>` has no stable layout, so a plugin is only safe to
+//! load when it was built against the same `plugin_manager` version with the
+//! same compiler as the host. Every plugin library exports an
+//! [`AbiDescriptor`] under [`ABI_DESCRIPTOR_SYMBOL`], which is checked before
+//! `create_plugins` is called. The [`declare_plugins!`](crate::declare_plugins)
+//! macro exports both symbols.
+
+use crate::PluginError;
+use std::ffi::{CStr, c_char};
+use std::path::Path;
+
+/// The version of the [`Plugin`](crate::Plugin) trait layout and of the
+/// [`AbiDescriptor`] itself. It is bumped whenever either of them changes.
+pub const PLUGIN_ABI_VERSION: u32 = 12;
+
+/// The name of the symbol under which plugins export their [`AbiDescriptor`].
+pub const ABI_DESCRIPTOR_SYMBOL: &str = "PLUGIN_ABI_DESCRIPTOR";
+
+/// The name of the function plugins export to create their plugins.
+pub const CREATE_PLUGINS_SYMBOL: &str = "create_plugins";
+
+/// Describes the ABI a plugin library was built with.
+///
+/// The layout is `#[repr(C)]` and `abi_version` always comes first, so a host
+/// can reject a descriptor from another version before reading the rest of it.
+///
+/// Plugin libraries normally export it through
+/// [`declare_plugins!`](crate::declare_plugins), which also records the name
+/// and version of the plugin package.
+///
+/// ```rust
+/// use plugin_manager::AbiDescriptor;
+///
+/// #[unsafe(no_mangle)]
+/// pub static PLUGIN_ABI_DESCRIPTOR: AbiDescriptor = AbiDescriptor::current();
+/// ```
+#[repr(C)]
+#[derive(Debug)]
+pub struct AbiDescriptor {
+    abi_version: u32,
+    crate_version: *const c_char,
+    rustc_version: *const c_char,
+    package_name: *const c_char,
+    package_version: *const c_char,
+}
+
+// The descriptor only points at static, immutable strings.
+unsafe impl Sync for AbiDescriptor {}
+
+impl AbiDescriptor {
+    /// The descriptor of the `plugin_manager` build in use.
+    pub const fn current() -> Self {
+        AbiDescriptor {
+            abi_version: PLUGIN_ABI_VERSION,
+            crate_version: concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast(),
+            rustc_version: concat!(env!("PLUGIN_MANAGER_RUSTC_VERSION"), "\0")
+                .as_ptr()
+                .cast(),
+            package_name: std::ptr::null(),
+            package_version: std::ptr::null(),
+        }
+    }
+
+    /// Records the name and version of the plugin package. Both strings must
+    /// be nul-terminated.
+    pub const fn with_package(mut self, name: &'static str, version: &'static str) -> Self {
+        assert!(
+            name.as_bytes()[name.len() - 1] == 0 && version.as_bytes()[version.len() - 1] == 0,
+            "package name and version must be nul-terminated"
+        );
+        self.package_name = name.as_ptr().cast();
+        self.package_version = version.as_ptr().cast();
+        self
+    }
+
+    /// The version of the [`Plugin`](crate::Plugin) trait layout.
+    pub fn abi_version(&self) -> u32 {
+        self.abi_version
+    }
+
+    /// The version of `plugin_manager` the library was built against.
+    pub fn crate_version(&self) -> &str {
+        Self::as_str(self.crate_version)
+    }
+
+    /// The version of `rustc` the library was built with.
+    pub fn rustc_version(&self) -> &str {
+        Self::as_str(self.rustc_version)
+    }
+
+    /// The name of the plugin package, if it was recorded.
+    pub fn package_name(&self) -> Option<&str> {
+        (!self.package_name.is_null()).then(|| Self::as_str(self.package_name))
+    }
+
+    /// The version of the plugin package, if it was recorded.
+    pub fn package_version(&self) -> Option<&str> {
+        (!self.package_version.is_null()).then(|| Self::as_str(self.package_version))
+    }
+
+    fn as_str<'a>(ptr: *const c_char) -> &'a str {
+        unsafe { CStr::from_ptr(ptr) }
+            .to_str()
+            .unwrap_or("<invalid>")
+    }
+
+    /// Checks that a descriptor exported by the library at `path` matches
+    /// the host's, comparing the ABI version before anything else.
+    pub(crate) fn check(&self, path: &Path) -> Result<(), PluginError> {
+        let current = AbiDescriptor::current();
+        let mismatch = |field: &'static str, expected: String, found: String| {
+            Err(PluginError::AbiMismatch {
+                path: path.to_path_buf(),
+                field,
+                expected,
+                found,
+            })
+        };
+        if self.abi_version != current.abi_version {
+            return mismatch(
+                "ABI version",
+                current.abi_version.to_string(),
+                self.abi_version.to_string(),
+            );
+        }
+        if self.crate_version() != current.crate_version() {
+            return mismatch(
+                "plugin_manager version",
+                current.crate_version().to_string(),
+                self.crate_version().to_string(),
+            );
+        }
+        if self.rustc_version() != current.rustc_version() {
+            return mismatch(
+                "rustc version",
+                current.rustc_version().to_string(),
+                self.rustc_version().to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Exports the `create_plugins` entry point and the [`AbiDescriptor`] of a
+/// plugin library.
+///
+/// Each argument is an expression evaluating to a type that implements
+/// [`Plugin`](crate::Plugin). The descriptor records the name and version of
+/// the package invoking the macro. A panic while the plugins are created is
+/// caught within the library and returned to the host as
+/// [`PluginError::PluginPanicked`].
+///
+/// ```rust
+/// use plugin_manager::{Plugin, declare_plugins};
+/// use std::any::Any;
+///
+/// #[derive(Debug)]
+/// struct PluginA;
+///
+/// impl Plugin for PluginA {
+///     fn as_any(&self) -> &dyn Any {
+///         self
+///     }
+///
+///     fn name(&self) -> String {
+///         "plugin_a".to_string()
+///     }
+///
+///     fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+///         Ok(())
+///     }
+/// }
+///
+/// declare_plugins!(PluginA);
+/// ```
+#[macro_export]
+macro_rules! declare_plugins {
+    ($($plugin:expr),* $(,)?) => {
+        #[unsafe(no_mangle)]
+        pub static PLUGIN_ABI_DESCRIPTOR: $crate::AbiDescriptor =
+            $crate::AbiDescriptor::current().with_package(
+                concat!(env!("CARGO_PKG_NAME"), "\0"),
+                concat!(env!("CARGO_PKG_VERSION"), "\0"),
+            );
+
+        #[unsafe(no_mangle)]
+        pub fn create_plugins() -> ::std::result::Result<
+            ::std::vec::Vec<::std::boxed::Box<dyn $crate::Plugin>>,
+            ::std::string::String,
+        > {
+            $crate::__private::catch_message(|| {
+                ::std::vec![$(::std::boxed::Box::new($plugin) as ::std::boxed::Box<dyn $crate::Plugin>),*]
+            })
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_abi_descriptor_test() {
+        let path = Path::new("libplugin.so");
+        assert!(AbiDescriptor::current().check(path).is_ok());
+
+        let descriptor = AbiDescriptor {
+            abi_version: PLUGIN_ABI_VERSION + 1,
+            ..AbiDescriptor::current()
+        };
+        let result = descriptor.check(path);
+        assert!(matches!(
+            result,
+            Err(PluginError::AbiMismatch {
+                field: "ABI version",
+                ..
+            })
+        ));
+
+        let descriptor = AbiDescriptor {
+            rustc_version: c"rustc 0.0.0".as_ptr(),
+            ..AbiDescriptor::current()
+        };
+        let result = descriptor.check(path);
+        assert!(matches!(
+            result,
+            Err(PluginError::AbiMismatch { field: "rustc version", found, .. }) if found == "rustc 0.0.0"
+        ));
+    }
+
+    #[test]
+    fn package_metadata_test() {
+        let descriptor = AbiDescriptor::current();
+        assert_eq!(descriptor.package_name(), None);
+
+        let descriptor = descriptor.with_package("plugin_mods\0", "0.1.0\0");
+        assert_eq!(descriptor.package_name(), Some("plugin_mods"));
+        assert_eq!(descriptor.package_version(), Some("0.1.0"));
+        assert!(descriptor.check(Path::new("libplugin_mods.so")).is_ok());
+    }
+}
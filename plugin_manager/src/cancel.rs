@@ -0,0 +1,32 @@
+//! Cooperative cancellation of plugin execution.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A flag a plugin checks to stop executing early.
+///
+/// The token is passed to [`Plugin::execute_cancellable`](crate::Plugin::execute_cancellable).
+/// Cancelling it does not interrupt the plugin: a long-running plugin is
+/// expected to check [`is_cancelled`](Self::is_cancelled) and return.
+/// Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the plugin to stop executing.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`cancel`](Self::cancel) has been called on this token or one
+    /// of its clones.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
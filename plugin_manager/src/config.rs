@@ -0,0 +1,563 @@
+//! Sources the plugin metadata can be read from.
+
+use crate::{Metadata, PathString, PluginEntry, PluginError, PluginSpec};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use std::env::consts::{DLL_EXTENSION, DLL_PREFIX};
+use std::path::{Component, Path, PathBuf};
+
+/// The file name looked up by [`PluginConfigSource::PluginsToml`].
+pub const PLUGINS_TOML: &str = "plugins.toml";
+
+/// Where the [`PluginManager`](crate::PluginManager) reads its plugin metadata from.
+///
+/// A Cargo manifest keeps the metadata under `[package.metadata]`, while a
+/// standalone file keeps it at the top level:
+///
+/// ```toml
+/// plugin_dirs = ["./plugins"]
+///
+/// [plugins]
+/// plugin_a = "/path/to/plugin_a.so"
+///
+/// [plugins.inventory]
+/// inventory_a = "/path/to/inventory_a.so"
+/// ```
+///
+/// Files given by path may use either layout.
+///
+/// Relative plugin paths are resolved against the directory of the file that
+/// declares them, or the working directory for inline metadata. Paths may
+/// start with `~` and contain `${ENV_VAR}` references.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PluginConfigSource {
+    /// The Cargo manifest named by the `CARGO_MANIFEST_PATH` environment variable.
+    #[default]
+    CargoManifest,
+    /// A Cargo manifest or standalone plugin file at an explicit path.
+    File(PathBuf),
+    /// A standalone `plugins.toml` in the working directory, or else next to
+    /// the running executable.
+    PluginsToml,
+    /// Standalone plugin metadata held in memory.
+    Inline(String),
+}
+
+impl PluginConfigSource {
+    /// The path of the file the metadata is read from, or `None` for inline
+    /// metadata and for a Cargo manifest when `CARGO_MANIFEST_PATH` is not set.
+    pub fn path(&self) -> Option<PathBuf> {
+        match self {
+            PluginConfigSource::CargoManifest => {
+                std::env::var_os("CARGO_MANIFEST_PATH").map(PathBuf::from)
+            }
+            PluginConfigSource::File(path) => Some(path.clone()),
+            PluginConfigSource::PluginsToml => {
+                let exe_dir = std::env::current_exe()
+                    .ok()
+                    .and_then(|exe| exe.parent().map(|dir| dir.join(PLUGINS_TOML)));
+                let candidates = [Some(PathBuf::from(PLUGINS_TOML)), exe_dir];
+                let found = candidates.iter().flatten().find(|path| path.is_file());
+                Some(
+                    found
+                        .cloned()
+                        .unwrap_or_else(|| PathBuf::from(PLUGINS_TOML)),
+                )
+            }
+            PluginConfigSource::Inline(_) => None,
+        }
+    }
+
+    /// Returns `true` if the source can be read without relying on a file
+    /// that has not been provided, such as an unset `CARGO_MANIFEST_PATH`.
+    pub(crate) fn is_available(&self) -> bool {
+        match self {
+            PluginConfigSource::CargoManifest => std::env::var_os("CARGO_MANIFEST_PATH").is_some(),
+            PluginConfigSource::PluginsToml => self.path().is_some_and(|path| path.is_file()),
+            PluginConfigSource::File(_) | PluginConfigSource::Inline(_) => true,
+        }
+    }
+
+    /// The path reported in errors about this source.
+    pub(crate) fn display_path(&self) -> PathBuf {
+        match (self, self.path()) {
+            (_, Some(path)) => path,
+            (PluginConfigSource::CargoManifest, None) => PathBuf::from("$CARGO_MANIFEST_PATH"),
+            (_, None) => PathBuf::from("<inline>"),
+        }
+    }
+
+    /// The directory relative plugin paths are resolved against.
+    fn base_dir(&self) -> PathBuf {
+        let current_dir = std::env::current_dir().unwrap_or_default();
+        match self.path() {
+            Some(path) => {
+                let dir = path.parent().unwrap_or(Path::new(""));
+                normalize(&current_dir.join(dir))
+            }
+            None => current_dir,
+        }
+    }
+
+    /// Reads and parses the plugin metadata, resolving every plugin path it contains.
+    pub(crate) fn load(&self) -> Result<Metadata, PluginError> {
+        let mut meta_data = self.load_unresolved()?;
+        meta_data.resolve_paths(&self.base_dir())?;
+        Ok(meta_data)
+    }
+
+    fn load_unresolved(&self) -> Result<Metadata, PluginError> {
+        let metadata_error = |source: Box<dyn std::error::Error + Send + Sync>| {
+            let error = PluginError::Metadata {
+                path: self.display_path(),
+                source,
+            };
+            log::error!("{error}");
+            error
+        };
+
+        let contents = match (self, self.path()) {
+            (PluginConfigSource::Inline(contents), _) => contents.clone(),
+            (_, Some(path)) => {
+                std::fs::read_to_string(path).map_err(|e| metadata_error(e.into()))?
+            }
+            (_, None) => {
+                return Err(metadata_error(
+                    "the CARGO_MANIFEST_PATH environment variable is not set".into(),
+                ));
+            }
+        };
+        let value: toml::Table = toml::from_str(&contents).map_err(|e| metadata_error(e.into()))?;
+        let meta_data = match self {
+            PluginConfigSource::CargoManifest => Self::package_metadata(&value),
+            _ if value.contains_key("package") => Self::package_metadata(&value),
+            _ => Some(value),
+        };
+        match meta_data {
+            Some(meta_data) => meta_data
+                .try_into()
+                .map_err(|e: toml::de::Error| metadata_error(e.into())),
+            None => Ok(Metadata::default()),
+        }
+    }
+
+    fn package_metadata(manifest: &toml::Table) -> Option<toml::Table> {
+        manifest
+            .get("package")
+            .and_then(|p| p.get("metadata"))
+            .and_then(|m| m.as_table())
+            .cloned()
+    }
+}
+
+/// A plugin library referenced by the crate that builds it rather than by
+/// its file name, which differs between platforms.
+///
+/// ```toml
+/// [package.metadata.plugins]
+/// plugin_mods = { crate = "plugin_mods", profile = "release", target_dir = "../target" }
+/// ```
+///
+/// expands to `../target/release/libplugin_mods.so` on Linux,
+/// `../target/release/libplugin_mods.dylib` on macOS and
+/// `../target/release/plugin_mods.dll` on Windows.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct CrateReference {
+    /// The name of the crate building the library.
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    /// The cargo profile the library was built with. Defaults to `release`.
+    #[serde(default = "CrateReference::default_profile")]
+    pub profile: String,
+    /// The cargo target directory. Defaults to `target`.
+    #[serde(default = "CrateReference::default_target_dir")]
+    pub target_dir: PathString,
+}
+
+impl CrateReference {
+    fn default_profile() -> String {
+        "release".to_string()
+    }
+
+    fn default_target_dir() -> PathString {
+        "target".to_string()
+    }
+
+    /// The platform-specific file name of the library, such as `libplugin_mods.so`.
+    pub fn file_name(&self) -> String {
+        format!(
+            "{DLL_PREFIX}{}.{DLL_EXTENSION}",
+            self.crate_name.replace('-', "_")
+        )
+    }
+
+    /// The path of the library within the target directory. The `dev`
+    /// profile builds into `debug`, as it does with cargo.
+    pub fn path(&self) -> PathBuf {
+        let profile_dir = match self.profile.as_str() {
+            "dev" | "test" => "debug",
+            "bench" => "release",
+            profile => profile,
+        };
+        Path::new(&self.target_dir)
+            .join(profile_dir)
+            .join(self.file_name())
+    }
+}
+
+/// A plugin path as written in the metadata: either a path or a [`CrateReference`].
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PathOrCrate {
+    Path(PathString),
+    Crate(CrateReference),
+}
+
+impl From<PathOrCrate> for PathString {
+    fn from(value: PathOrCrate) -> Self {
+        match value {
+            PathOrCrate::Path(path) => path,
+            PathOrCrate::Crate(crate_ref) => crate_ref.path().to_string_lossy().to_string(),
+        }
+    }
+}
+
+/// Deserializes a plugin path, expanding a [`CrateReference`] into the path
+/// of its library.
+pub(crate) fn path_or_crate<'de, D>(deserializer: D) -> Result<PathString, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    PathOrCrate::deserialize(deserializer).map(PathString::from)
+}
+
+impl Metadata {
+    /// Expands and resolves every plugin path against `base_dir`.
+    fn resolve_paths(&mut self, base_dir: &Path) -> Result<(), PluginError> {
+        for plugin_entry in self
+            .plugins
+            .iter_mut()
+            .flat_map(|plugins| plugins.values_mut())
+        {
+            match plugin_entry {
+                PluginEntry::Individual(path) => *path = resolve_path(path, base_dir)?,
+                PluginEntry::Detailed(spec) => spec.path = resolve_path(&spec.path, base_dir)?,
+                PluginEntry::Group(group_plugins) => {
+                    for path in group_plugins.values_mut() {
+                        *path = resolve_path(path, base_dir)?;
+                    }
+                }
+            }
+        }
+        for plugin_dir in self.plugin_dirs.iter_mut().flatten() {
+            plugin_dir.path = resolve_path(&plugin_dir.path, base_dir)?;
+        }
+        Ok(())
+    }
+}
+
+/// Expands `~` and `${ENV_VAR}` references in `path` and resolves it against
+/// `base_dir` when it is relative.
+pub(crate) fn resolve_path(path: &str, base_dir: &Path) -> Result<PathString, PluginError> {
+    let expanded = expand_path(path)?;
+    let resolved = normalize(&base_dir.join(expanded));
+    match resolved.to_str() {
+        Some(resolved) => Ok(resolved.to_string()),
+        None => Err(PluginError::InvalidPath { path: resolved }),
+    }
+}
+
+fn expand_path(path: &str) -> Result<String, PluginError> {
+    let undefined = |variable: &str| PluginError::UndefinedVariable {
+        variable: variable.to_string(),
+        path: path.to_string(),
+    };
+
+    let mut expanded = String::with_capacity(path.len());
+    let mut rest = path;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| undefined(&rest[start + 2..]))?;
+        let variable = &rest[start + 2..end];
+        let value = std::env::var(variable).map_err(|_| undefined(variable))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+
+    if expanded == "~" || expanded.starts_with("~/") || expanded.starts_with("~\\") {
+        let home_var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+        let home = std::env::var(home_var).map_err(|_| undefined(home_var))?;
+        expanded.replace_range(..1, &home);
+    }
+    Ok(expanded)
+}
+
+/// Removes `.` and `..` components without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// The keys of the table form of a plugin entry, [`PluginSpec`].
+const PLUGIN_SPEC_FIELDS: &[&str] = &[
+    "path",
+    "group",
+    "enabled",
+    "priority",
+    "config",
+    "depends_on",
+    "version",
+];
+
+/// Tells the forms of a plugin entry apart by their shape:
+///
+/// - a path, or a [`CrateReference`] table, is an individual plugin;
+/// - a table with a `path` whose keys are all fields of [`PluginSpec`],
+///   including `path` on its own, is the table form;
+/// - a table whose values are all paths is a group;
+/// - any other table with a `path` is read as the table form, so that a
+///   misspelled key is reported by name.
+impl<'de> Deserialize<'de> for PluginEntry {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let table = match toml::Value::deserialize(deserializer)? {
+            toml::Value::String(path) => return Ok(PluginEntry::Individual(path)),
+            toml::Value::Table(table) => table,
+            value => {
+                return Err(D::Error::custom(format!(
+                    "invalid plugin entry: expected a path or a table, found {}",
+                    value.type_str()
+                )));
+            }
+        };
+        if is_crate_reference(&table) {
+            return path_or_crate(toml::Value::Table(table))
+                .map(PluginEntry::Individual)
+                .map_err(D::Error::custom);
+        }
+        let has_path = table.contains_key("path");
+        let is_spec = has_path
+            && table
+                .keys()
+                .all(|key| PLUGIN_SPEC_FIELDS.contains(&key.as_str()));
+        if is_spec || (has_path && !table.values().all(is_path)) {
+            return PluginSpec::deserialize(toml::Value::Table(table))
+                .map(PluginEntry::Detailed)
+                .map_err(D::Error::custom);
+        }
+        table
+            .into_iter()
+            .map(|(name, path)| match path_or_crate(path) {
+                Ok(path) => Ok((name, path)),
+                Err(error) => Err(D::Error::custom(format!(
+                    "invalid path for plugin `{name}`: {error}"
+                ))),
+            })
+            .collect::<Result<_, _>>()
+            .map(PluginEntry::Group)
+    }
+}
+
+/// Whether a table has the keys of a [`CrateReference`].
+fn is_crate_reference(table: &toml::Table) -> bool {
+    table.contains_key("crate")
+        && table
+            .keys()
+            .all(|key| ["crate", "profile", "target_dir"].contains(&key.as_str()))
+}
+
+/// Whether a value can be read as a plugin path.
+fn is_path(value: &toml::Value) -> bool {
+    match value {
+        toml::Value::String(_) => true,
+        toml::Value::Table(table) => is_crate_reference(table),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_inline_metadata_test() {
+        let source = PluginConfigSource::Inline(
+            r#"
+            plugin_dirs = ["./plugins"]
+
+            [plugins]
+            plugin_a = "/path/to/plugin_a.so"
+            "#
+            .to_string(),
+        );
+        let metadata = source.load().unwrap();
+        assert_eq!(metadata.plugins.unwrap().len(), 1);
+        assert_eq!(metadata.plugin_dirs.unwrap().len(), 1);
+
+        let source = PluginConfigSource::Inline("plugins = 1".to_string());
+        let result = source.load();
+        assert!(
+            matches!(result, Err(PluginError::Metadata { path, .. }) if path.as_os_str() == "<inline>")
+        );
+    }
+
+    #[test]
+    fn plugin_entry_forms_test() {
+        let metadata: Metadata = toml::from_str(
+            r#"
+            [plugins]
+            single = "libsingle.so"
+            detailed = { path = "libdetailed.so", group = "tools" }
+
+            [plugins.tools]
+            path = "libpath.so"
+            other = "libother.so"
+
+            [plugins.only_path]
+            path = "libonly_path.so"
+            "#,
+        )
+        .unwrap();
+        let plugins = metadata.plugins.unwrap();
+        assert!(
+            matches!(&plugins["single"], PluginEntry::Individual(path) if path == "libsingle.so")
+        );
+        assert!(
+            matches!(&plugins["detailed"], PluginEntry::Detailed(spec) if spec.group.as_deref() == Some("tools"))
+        );
+        match &plugins["tools"] {
+            PluginEntry::Group(group) => {
+                assert_eq!(group.keys().collect::<Vec<_>>(), ["path", "other"]);
+            }
+            entry => panic!("expected a group, found {entry:?}"),
+        }
+        assert!(
+            matches!(&plugins["only_path"], PluginEntry::Detailed(spec) if spec.path == "libonly_path.so" && spec.group.is_none())
+        );
+    }
+
+    #[test]
+    fn plugin_entry_error_test() {
+        let error = toml::from_str::<Metadata>(
+            r#"
+            [plugins]
+            plugin_a = { path = "libplugin_a.so", enabeld = false }
+            "#,
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("unknown field `enabeld`"),
+            "{error}"
+        );
+
+        let error = toml::from_str::<Metadata>(
+            r#"
+            [plugins.tools]
+            plugin_a = "libplugin_a.so"
+            plugin_b = 1
+            "#,
+        )
+        .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("invalid path for plugin `plugin_b`"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn resolve_path_test() {
+        let base_dir = Path::new("/srv/app/config");
+        assert_eq!(
+            resolve_path("../plugins/./libplugin.so", base_dir).unwrap(),
+            "/srv/app/plugins/libplugin.so"
+        );
+        assert_eq!(
+            resolve_path("/opt/libplugin.so", base_dir).unwrap(),
+            "/opt/libplugin.so"
+        );
+
+        unsafe {
+            std::env::set_var("PLUGIN_MANAGER_TEST_DIR", "/opt/plugins");
+        }
+        assert_eq!(
+            resolve_path("${PLUGIN_MANAGER_TEST_DIR}/libplugin.so", base_dir).unwrap(),
+            "/opt/plugins/libplugin.so"
+        );
+        let result = resolve_path("${PLUGIN_MANAGER_UNSET_DIR}/libplugin.so", base_dir);
+        assert!(matches!(
+            result,
+            Err(PluginError::UndefinedVariable { variable, .. }) if variable == "PLUGIN_MANAGER_UNSET_DIR"
+        ));
+
+        if let Ok(home) = std::env::var("HOME") {
+            assert_eq!(
+                resolve_path("~/libplugin.so", base_dir).unwrap(),
+                format!("{home}/libplugin.so")
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn crate_reference_test() {
+        let source = PluginConfigSource::Inline(
+            r#"
+            [plugins]
+            plugin_mods = { crate = "plugin-mods", target_dir = "/opt/target" }
+
+            [plugins.inventory]
+            inventory_a = { crate = "plugin_inventory", profile = "dev", target_dir = "/opt/target" }
+            "#
+            .to_string(),
+        );
+        let plugins = source.load().unwrap().plugins.unwrap();
+        let expected = Path::new("/opt/target")
+            .join("release")
+            .join(format!("{DLL_PREFIX}plugin_mods.{DLL_EXTENSION}"));
+        assert!(matches!(
+            &plugins["plugin_mods"],
+            PluginEntry::Individual(path) if Path::new(path) == normalize(&expected)
+        ));
+        let expected = Path::new("/opt/target")
+            .join("debug")
+            .join(format!("{DLL_PREFIX}plugin_inventory.{DLL_EXTENSION}"));
+        assert!(matches!(
+            &plugins["inventory"],
+            PluginEntry::Group(group) if Path::new(&group["inventory_a"]) == normalize(&expected)
+        ));
+    }
+
+    #[test]
+    fn load_manifest_file_test() {
+        let source = PluginConfigSource::File("../tests/plugin_mods/Cargo.toml".into());
+        let metadata = source.load().unwrap();
+        let plugins = metadata.plugins.unwrap();
+        assert!(plugins.contains_key("inventory"));
+
+        let source = PluginConfigSource::File("../tests/plugin_mods/missing.toml".into());
+        assert!(matches!(source.load(), Err(PluginError::Metadata { .. })));
+    }
+}
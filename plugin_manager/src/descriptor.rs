@@ -0,0 +1,135 @@
+//! Descriptive metadata of registered plugins.
+
+use crate::{HookSubscription, Plugin, PluginError};
+use semver::Version;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// What a plugin says about itself, along with where it was loaded from.
+///
+/// The manager builds a descriptor from the [`Plugin`] methods when the
+/// plugin is registered, and fills in the `path` and `library_hash` of the
+/// shared object it came from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PluginDescriptor {
+    pub name: String,
+    /// The version reported by [`Plugin::version`].
+    pub version: Option<Version>,
+    pub description: Option<String>,
+    pub authors: Vec<String>,
+    /// Free-form labels used to find plugins with [`PluginManager::find_by_tag`](crate::PluginManager::find_by_tag).
+    pub tags: Vec<String>,
+    /// What the plugin can do, such as `"export-csv"`, used to find plugins
+    /// with [`PluginManager::find_by_capability`](crate::PluginManager::find_by_capability).
+    pub capabilities: Vec<String>,
+    /// The extension points the plugin subscribes to, from [`Plugin::hooks`].
+    pub hooks: Vec<HookSubscription>,
+    /// The shared object the plugin was loaded from, if any.
+    pub path: Option<PathBuf>,
+    /// The SHA-256 digest of the shared object, in lowercase hex.
+    pub library_hash: Option<String>,
+}
+
+impl PluginDescriptor {
+    /// Collects the metadata reported by a plugin.
+    ///
+    /// Fails with [`PluginError::InvalidVersion`] if the plugin reports a
+    /// version that is not valid semver, and with
+    /// [`PluginError::PluginPanicked`] if it panics while listing its hooks.
+    pub fn from_plugin(plugin: &dyn Plugin) -> Result<Self, PluginError> {
+        let version = plugin
+            .version()
+            .map(|version| {
+                Version::parse(&version).map_err(|source| PluginError::InvalidVersion {
+                    name: plugin.name(),
+                    version,
+                    source,
+                })
+            })
+            .transpose()?;
+        let hooks = plugin
+            .try_hooks()
+            .map_err(|error| PluginError::execution(plugin, &plugin.name(), error))?;
+        Ok(PluginDescriptor {
+            name: plugin.name(),
+            version,
+            description: plugin.description(),
+            authors: plugin.authors(),
+            tags: plugin.tags(),
+            capabilities: plugin.capabilities(),
+            hooks,
+            path: None,
+            library_hash: None,
+        })
+    }
+
+    /// Whether the plugin has the given tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Whether the plugin declares the given capability.
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+}
+
+/// The SHA-256 digest of the file at `path`, in lowercase hex.
+pub(crate) fn library_hash(path: &Path) -> std::io::Result<String> {
+    let digest = Sha256::digest(std::fs::read(path)?);
+    Ok(digest.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Execute;
+    use std::any::Any;
+
+    #[derive(Debug, crate::Plugin)]
+    #[plugin(
+        name = "csv_exporter",
+        version = "1.2.0",
+        description = "Exports the inventory as CSV",
+        authors("Jane Doe"),
+        tags("export", "inventory"),
+        capabilities("export-csv")
+    )]
+    struct CsvExporter;
+
+    impl Execute for CsvExporter {
+        fn execute(
+            &self,
+            _context: &dyn Any,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn from_plugin_test() {
+        let descriptor = PluginDescriptor::from_plugin(&CsvExporter).unwrap();
+        assert_eq!(descriptor.name, "csv_exporter");
+        assert_eq!(descriptor.version, Some(Version::new(1, 2, 0)));
+        assert_eq!(
+            descriptor.description.as_deref(),
+            Some("Exports the inventory as CSV")
+        );
+        assert_eq!(descriptor.authors, ["Jane Doe"]);
+        assert!(descriptor.has_tag("inventory"));
+        assert!(!descriptor.has_tag("import"));
+        assert!(descriptor.has_capability("export-csv"));
+        assert_eq!(descriptor.path, None);
+    }
+
+    #[test]
+    fn library_hash_test() {
+        let path = std::env::temp_dir().join("plugin_manager_library_hash_test");
+        std::fs::write(&path, b"abc").unwrap();
+        assert_eq!(
+            library_hash(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+//! Discovery of plugin libraries by scanning directories.
+
+use crate::{PathString, PluginError};
+use serde::Deserialize;
+use std::env::consts::{DLL_EXTENSION, DLL_PREFIX};
+use std::path::{Path, PathBuf};
+
+/// A directory scanned for plugin libraries.
+///
+/// Every file named `{DLL_PREFIX}*.{DLL_EXTENSION}` in the directory is
+/// loaded, optionally narrowed down by a glob `pattern` on the file name.
+/// In the manifest a directory is either a path or a table:
+///
+/// ```toml
+/// [package.metadata]
+/// plugin_dirs = [
+///     "./plugins",
+///     { path = "./extra", recursive = true, pattern = "*inventory*", group = "inventory" },
+/// ]
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "PluginDirRepr")]
+pub struct PluginDir {
+    pub path: PathString,
+    /// Whether subdirectories are scanned as well.
+    pub recursive: bool,
+    /// A glob the file name must match, such as `"*inventory*"`.
+    pub pattern: Option<String>,
+    /// The group the discovered plugins are registered under.
+    pub group: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PluginDirRepr {
+    Path(PathString),
+    Options {
+        path: PathString,
+        #[serde(default)]
+        recursive: bool,
+        pattern: Option<String>,
+        group: Option<String>,
+    },
+}
+
+impl From<PluginDirRepr> for PluginDir {
+    fn from(repr: PluginDirRepr) -> Self {
+        match repr {
+            PluginDirRepr::Path(path) => PluginDir::new(path),
+            PluginDirRepr::Options {
+                path,
+                recursive,
+                pattern,
+                group,
+            } => PluginDir {
+                path,
+                recursive,
+                pattern,
+                group,
+            },
+        }
+    }
+}
+
+impl PluginDir {
+    /// A non-recursive scan of `path` for every plugin library.
+    pub fn new(path: impl Into<PathString>) -> Self {
+        PluginDir {
+            path: path.into(),
+            recursive: false,
+            pattern: None,
+            group: None,
+        }
+    }
+
+    /// Sets whether subdirectories are scanned as well.
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Only loads libraries whose file name matches the glob `pattern`.
+    pub fn pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.pattern = Some(pattern.into());
+        self
+    }
+
+    /// Registers the discovered plugins under `group`.
+    pub fn group(mut self, group: impl Into<String>) -> Self {
+        self.group = Some(group.into());
+        self
+    }
+
+    /// Lists the plugin libraries in the directory, sorted by path.
+    ///
+    /// A recursive scan does not descend into symlinked directories.
+    pub fn scan(&self) -> Result<Vec<PathBuf>, PluginError> {
+        let root = Path::new(&self.path);
+        let pattern =
+            match &self.pattern {
+                Some(pattern) => Some(glob::Pattern::new(pattern).map_err(|source| {
+                    PluginError::DirectoryScan {
+                        path: root.to_path_buf(),
+                        source: source.into(),
+                    }
+                })?),
+                None => None,
+            };
+
+        let mut libraries = Vec::new();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let entries = std::fs::read_dir(&dir).map_err(|source| PluginError::DirectoryScan {
+                path: dir.clone(),
+                source: source.into(),
+            })?;
+            for entry in entries {
+                let entry = entry.map_err(|source| PluginError::DirectoryScan {
+                    path: dir.clone(),
+                    source: source.into(),
+                })?;
+                let path = entry.path();
+                if path.is_dir() {
+                    // Symlinked directories are not followed, as they may form a cycle.
+                    let is_symlink = entry.file_type().is_ok_and(|t| t.is_symlink());
+                    if self.recursive && !is_symlink {
+                        pending.push(path);
+                    } else if is_symlink {
+                        log::debug!("Skipping symlinked directory {}", path.display());
+                    }
+                } else if Self::is_library(&path, pattern.as_ref()) {
+                    libraries.push(path);
+                }
+            }
+        }
+        libraries.sort();
+        log::debug!(
+            "Discovered {} plugin libraries in {}",
+            libraries.len(),
+            self.path
+        );
+        Ok(libraries)
+    }
+
+    fn is_library(path: &Path, pattern: Option<&glob::Pattern>) -> bool {
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+        file_name.starts_with(DLL_PREFIX)
+            && path.extension().and_then(|ext| ext.to_str()) == Some(DLL_EXTENSION)
+            && pattern.is_none_or(|pattern| pattern.matches(file_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_plugin_dir_test() {
+        let libraries = PluginDir::new("../target/release")
+            .pattern("*plugin_*")
+            .scan()
+            .unwrap();
+        let names: Vec<String> = libraries
+            .iter()
+            .map(|path| path.file_stem().unwrap().to_string_lossy().to_string())
+            .collect();
+        for name in ["plugin_inventory", "plugin_mods", "plugin_tasks"] {
+            assert!(names.contains(&format!("{DLL_PREFIX}{name}")));
+        }
+
+        let libraries = PluginDir::new("../target/release")
+            .pattern("*plugin_tasks*")
+            .scan()
+            .unwrap();
+        assert_eq!(libraries.len(), 1);
+
+        let result = PluginDir::new("../target/does_not_exist").scan();
+        assert!(matches!(result, Err(PluginError::DirectoryScan { .. })));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_symlink_cycle_test() {
+        let root = std::env::temp_dir().join("plugin_manager_symlink_cycle_test");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("nested/loop")).unwrap();
+        let library = root.join(format!("{DLL_PREFIX}plugin_cycle.{DLL_EXTENSION}"));
+        std::fs::write(&library, b"").unwrap();
+
+        let libraries = PluginDir::new(root.to_str().unwrap())
+            .recursive(true)
+            .scan()
+            .unwrap();
+        assert_eq!(libraries, [library]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn deserialize_plugin_dirs_test() {
+        #[derive(Deserialize)]
+        struct Dirs {
+            plugin_dirs: Vec<PluginDir>,
+        }
+        let dirs: Dirs = toml::from_str(
+            r#"plugin_dirs = ["./plugins", { path = "./extra", recursive = true, group = "inventory" }]"#,
+        )
+        .unwrap();
+        assert_eq!(dirs.plugin_dirs[0], PluginDir::new("./plugins"));
+        assert_eq!(
+            dirs.plugin_dirs[1],
+            PluginDir::new("./extra").recursive(true).group("inventory")
+        );
+    }
+}
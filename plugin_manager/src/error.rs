@@ -0,0 +1,310 @@
+//! Error types returned by the [`PluginManager`](crate::PluginManager).
+
+use crate::Plugin;
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Errors produced while loading, registering and executing plugins.
+///
+/// Each variant carries the plugin name or the path of the shared object
+/// involved, along with the underlying error where there is one, so callers
+/// can react to specific failures without inspecting the message.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PluginError {
+    /// The shared object file does not exist.
+    FileNotFound { path: PathBuf },
+    /// A plugin path refers to an environment variable that is not set.
+    UndefinedVariable { variable: String, path: String },
+    /// The path cannot be represented as valid Unicode.
+    InvalidPath { path: PathBuf },
+    /// The dynamic loader failed to open the shared object.
+    LibraryLoad {
+        path: PathBuf,
+        source: libloading::Error,
+    },
+    /// The shared object does not export a required symbol.
+    MissingSymbol {
+        path: PathBuf,
+        symbol: String,
+        source: libloading::Error,
+    },
+    /// The library was built against an incompatible `plugin_manager` or `rustc`.
+    AbiMismatch {
+        path: PathBuf,
+        field: &'static str,
+        expected: String,
+        found: String,
+    },
+    /// The version reported by the plugin is not a valid semver version.
+    InvalidVersion {
+        name: String,
+        version: String,
+        source: semver::Error,
+    },
+    /// The version of the plugin does not satisfy the requirement of its entry.
+    VersionMismatch {
+        name: String,
+        required: semver::VersionReq,
+        found: Option<semver::Version>,
+    },
+    /// A plugin depends on a plugin that is neither registered nor being activated.
+    MissingDependency { name: String, dependency: String },
+    /// A plugin is part of a cycle of dependencies between the plugins being
+    /// activated. Plugins depending on the cycle fail with
+    /// [`MissingDependency`](PluginError::MissingDependency).
+    DependencyCycle { name: String, cycle: Vec<String> },
+    /// The plugin cannot be deregistered while other plugins depend on it.
+    HasDependents {
+        name: String,
+        dependents: Vec<String>,
+    },
+    /// No registered plugin was loaded from the shared object.
+    LibraryNotLoaded { path: PathBuf },
+    /// A plugin with the same name has already been registered.
+    DuplicatePlugin { name: String, group: Option<String> },
+    /// A plugin directory could not be scanned.
+    DirectoryScan { path: PathBuf, source: BoxError },
+    /// The plugin metadata could not be read or parsed.
+    Metadata { path: PathBuf, source: BoxError },
+    /// No plugin is registered under the given name.
+    NotFound { name: String },
+    /// The plugin is not of the type it was downcast to.
+    Downcast {
+        name: String,
+        type_name: &'static str,
+    },
+    /// The plugin rejected the `config` table of its manifest entry.
+    Configuration { name: String, source: BoxError },
+    /// A lifecycle hook of the plugin, such as `on_load`, returned an error.
+    Lifecycle {
+        name: String,
+        hook: &'static str,
+        source: BoxError,
+    },
+    /// The plugin was executed with a context of another type than it expects.
+    /// The type name is owned, since it may come from a plugin library that
+    /// is closed before the error is dropped.
+    ContextMismatch { name: String, expected: String },
+    /// The output of the plugin is not of the requested type.
+    OutputMismatch {
+        name: String,
+        expected: &'static str,
+    },
+    /// The plugin, or the `create_plugins` function of a library, panicked.
+    PluginPanicked { name: String, message: String },
+    /// The plugin did not finish executing within the timeout.
+    Timeout { name: String, timeout: Duration },
+    /// The plugin panicked before and is no longer executed.
+    Quarantined { name: String },
+    /// The extension point was not declared with
+    /// [`PluginManager::with_hook`](crate::PluginManager::with_hook).
+    UnknownHook { point: String },
+    /// The plugin returned an error while executing.
+    Execution { name: String, source: BoxError },
+}
+
+impl PluginError {
+    /// Wraps an error returned by a plugin's `execute` method, keeping it
+    /// as the [`source`](Error::source). A `PluginError`, such as the
+    /// [`ContextMismatch`](PluginError::ContextMismatch) of a typed plugin,
+    /// is returned as is, even when it comes from a plugin library.
+    pub(crate) fn execution(plugin: &dyn Plugin, name: &str, error: BoxError) -> Self {
+        if let Some(error) = plugin.plugin_error(error.as_ref()) {
+            return error;
+        }
+        match error.downcast::<PluginError>() {
+            Ok(error) => *error,
+            Err(source) => PluginError::Execution {
+                name: name.to_string(),
+                source,
+            },
+        }
+    }
+
+    /// Wraps an error returned by one of a plugin's lifecycle hooks. A
+    /// panic caught within the plugin is returned as is.
+    pub(crate) fn lifecycle(
+        plugin: &dyn Plugin,
+        name: &str,
+        hook: &'static str,
+        source: BoxError,
+    ) -> Self {
+        PluginError::panicked(plugin, source).unwrap_or_else(|source| PluginError::Lifecycle {
+            name: name.to_string(),
+            hook,
+            source,
+        })
+    }
+
+    /// Wraps an error returned by a plugin's `configure` method. A panic
+    /// caught within the plugin is returned as is.
+    pub(crate) fn configuration(plugin: &dyn Plugin, name: &str, source: BoxError) -> Self {
+        PluginError::panicked(plugin, source).unwrap_or_else(|source| PluginError::Configuration {
+            name: name.to_string(),
+            source,
+        })
+    }
+
+    /// Takes a panic caught within the plugin, possibly in its own library,
+    /// out of an error returned by the plugin.
+    fn panicked(plugin: &dyn Plugin, error: BoxError) -> Result<Self, BoxError> {
+        match plugin.plugin_error(error.as_ref()) {
+            Some(error @ PluginError::PluginPanicked { .. }) => Ok(error),
+            _ => Err(error),
+        }
+    }
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginError::FileNotFound { path } => {
+                write!(f, "Plugin file does not exist: {}", path.display())
+            }
+            PluginError::UndefinedVariable { variable, path } => {
+                write!(
+                    f,
+                    "Environment variable '{variable}' used in plugin path '{path}' is not set"
+                )
+            }
+            PluginError::InvalidPath { path } => {
+                write!(f, "Path contains invalid Unicode: {}", path.display())
+            }
+            PluginError::LibraryLoad { path, source } => {
+                write!(f, "Failed to load library {}: {source}", path.display())
+            }
+            PluginError::MissingSymbol { path, symbol, .. } => {
+                write!(f, "Symbol '{symbol}' not found in {}", path.display())
+            }
+            PluginError::AbiMismatch {
+                path,
+                field,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "Plugin library {} is incompatible: {field} is {found}, expected {expected}",
+                    path.display()
+                )
+            }
+            PluginError::InvalidVersion {
+                name,
+                version,
+                source,
+            } => {
+                write!(
+                    f,
+                    "Plugin '{name}' has an invalid version '{version}': {source}"
+                )
+            }
+            PluginError::VersionMismatch {
+                name,
+                required,
+                found,
+            } => match found {
+                Some(found) => write!(
+                    f,
+                    "Plugin '{name}' version {found} does not satisfy the requirement {required}"
+                ),
+                None => write!(
+                    f,
+                    "Plugin '{name}' has no version, but {required} is required"
+                ),
+            },
+            PluginError::MissingDependency { name, dependency } => {
+                write!(
+                    f,
+                    "Plugin '{name}' depends on '{dependency}', which is not available"
+                )
+            }
+            PluginError::DependencyCycle { name, cycle } => {
+                write!(
+                    f,
+                    "Plugin '{name}' is part of a dependency cycle between: {}",
+                    cycle.join(", ")
+                )
+            }
+            PluginError::HasDependents { name, dependents } => {
+                write!(
+                    f,
+                    "Plugin '{name}' is required by: {}",
+                    dependents.join(", ")
+                )
+            }
+            PluginError::LibraryNotLoaded { path } => {
+                write!(f, "No plugins loaded from library: {}", path.display())
+            }
+            PluginError::DuplicatePlugin { name, .. } => {
+                write!(f, "Plugin '{name}' already registered")
+            }
+            PluginError::DirectoryScan { path, source } => {
+                write!(
+                    f,
+                    "Failed to scan plugin directory {}: {source}",
+                    path.display()
+                )
+            }
+            PluginError::Metadata { path, source } => {
+                write!(
+                    f,
+                    "Failed to read plugin metadata from {}: {source}",
+                    path.display()
+                )
+            }
+            PluginError::NotFound { name } => write!(f, "Plugin '{name}' not found"),
+            PluginError::Downcast { name, type_name } => {
+                write!(f, "Failed to downcast plugin '{name}' to {type_name}")
+            }
+            PluginError::Configuration { name, source } => {
+                write!(f, "Plugin '{name}' rejected its configuration: {source}")
+            }
+            PluginError::Lifecycle { name, hook, source } => {
+                write!(f, "Plugin '{name}' failed in {hook}: {source}")
+            }
+            PluginError::ContextMismatch { name, expected } => {
+                write!(f, "Plugin '{name}' expects a context of type {expected}")
+            }
+            PluginError::OutputMismatch { name, expected } => {
+                write!(f, "Plugin '{name}' does not return a {expected}")
+            }
+            PluginError::PluginPanicked { name, message } => {
+                write!(f, "Plugin '{name}' panicked: {message}")
+            }
+            PluginError::Timeout { name, timeout } => {
+                write!(f, "Plugin '{name}' did not finish within {timeout:?}")
+            }
+            PluginError::Quarantined { name } => {
+                write!(f, "Plugin '{name}' is quarantined after panicking")
+            }
+            PluginError::UnknownHook { point } => {
+                write!(f, "Extension point '{point}' is not declared")
+            }
+            PluginError::Execution { name, source } => {
+                write!(f, "Plugin '{name}' failed to execute: {source}")
+            }
+        }
+    }
+}
+
+impl Error for PluginError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PluginError::LibraryLoad { source, .. } | PluginError::MissingSymbol { source, .. } => {
+                Some(source)
+            }
+            PluginError::InvalidVersion { source, .. } => Some(source),
+            PluginError::DirectoryScan { source, .. }
+            | PluginError::Metadata { source, .. }
+            | PluginError::Configuration { source, .. }
+            | PluginError::Lifecycle { source, .. }
+            | PluginError::Execution { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
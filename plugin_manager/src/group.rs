@@ -0,0 +1,127 @@
+//! Batch execution of the plugins in a group.
+
+use crate::PluginError;
+use indexmap::IndexMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// How [`PluginManager::execute_group`](crate::PluginManager::execute_group)
+/// runs the plugins of a group.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Run the plugins one after the other, in registration order.
+    #[default]
+    Sequential,
+    /// Run the plugins on a pool of at most `threads` worker threads, which
+    /// take them in registration order. A pool of zero threads is treated as
+    /// one thread.
+    Parallel { threads: usize },
+}
+
+/// What [`PluginManager::execute_group`](crate::PluginManager::execute_group)
+/// does once a plugin fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// Start no further plugins. Plugins already running in parallel still
+    /// finish and are reported.
+    FailFast,
+    /// Run every plugin and report all the errors.
+    #[default]
+    CollectAll,
+}
+
+/// The outcome of [`PluginManager::execute_group`](crate::PluginManager::execute_group).
+#[derive(Debug, Default)]
+pub struct GroupReport {
+    /// The result of each plugin that was executed, keyed by plugin name, in
+    /// registration order.
+    pub results: IndexMap<String, Result<(), PluginError>>,
+    /// The plugins that were not started because of [`FailurePolicy::FailFast`].
+    pub skipped: Vec<String>,
+}
+
+impl GroupReport {
+    /// Pairs the plugin names with their results, skipping those without one.
+    fn new(names: Vec<String>, results: Vec<Option<Result<(), PluginError>>>) -> Self {
+        let mut report = GroupReport::default();
+        for (name, result) in names.into_iter().zip(results) {
+            match result {
+                Some(result) => {
+                    report.results.insert(name, result);
+                }
+                None => report.skipped.push(name),
+            }
+        }
+        report
+    }
+
+    /// Returns `true` if every plugin was executed and succeeded.
+    pub fn is_success(&self) -> bool {
+        self.skipped.is_empty() && self.results.values().all(Result::is_ok)
+    }
+
+    /// The plugins that failed, and why, in registration order.
+    pub fn failures(&self) -> impl Iterator<Item = (&String, &PluginError)> {
+        self.results
+            .iter()
+            .filter_map(|(name, result)| result.as_ref().err().map(|error| (name, error)))
+    }
+}
+
+/// Runs `run` for each of `names` as set by `mode` and `policy`.
+pub(crate) fn execute(
+    names: Vec<String>,
+    mode: ExecutionMode,
+    policy: FailurePolicy,
+    run: impl Fn(&str) -> Result<(), PluginError> + Sync,
+) -> GroupReport {
+    let fail_fast = policy == FailurePolicy::FailFast;
+    let results = match mode {
+        ExecutionMode::Sequential => {
+            let mut failed = false;
+            names
+                .iter()
+                .map(|name| {
+                    if failed {
+                        return None;
+                    }
+                    let result = run(name);
+                    failed = fail_fast && result.is_err();
+                    Some(result)
+                })
+                .collect()
+        }
+        ExecutionMode::Parallel { threads } => {
+            let next = AtomicUsize::new(0);
+            let failed = AtomicBool::new(false);
+            let worker = || {
+                let mut results = Vec::new();
+                while !failed.load(Ordering::Relaxed) {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(name) = names.get(index) else {
+                        break;
+                    };
+                    let result = run(name);
+                    if fail_fast && result.is_err() {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                    results.push((index, result));
+                }
+                results
+            };
+            let mut results: Vec<_> = names.iter().map(|_| None).collect();
+            std::thread::scope(|scope| {
+                let workers: Vec<_> = (0..threads.clamp(1, names.len().max(1)))
+                    .map(|_| scope.spawn(worker))
+                    .collect();
+                for worker in workers {
+                    // `run` catches plugin panics, so the workers do not panic.
+                    for (index, result) in worker.join().unwrap_or_default() {
+                        results[index] = Some(result);
+                    }
+                }
+            });
+            results
+        }
+    };
+    GroupReport::new(names, results)
+}
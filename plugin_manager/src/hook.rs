@@ -0,0 +1,55 @@
+//! Named extension points that plugins subscribe to.
+
+use crate::PluginError;
+use indexmap::IndexMap;
+use std::any::Any;
+
+/// A plugin's subscription to an extension point declared by the host with
+/// [`PluginManager::with_hook`](crate::PluginManager::with_hook).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookSubscription {
+    /// The name of the extension point, such as `"before_save"`.
+    pub point: String,
+    /// Subscribers with a higher priority are called first.
+    pub priority: i32,
+}
+
+impl HookSubscription {
+    pub fn new(point: &str, priority: i32) -> Self {
+        HookSubscription {
+            point: point.to_string(),
+            priority,
+        }
+    }
+}
+
+/// The outcome of [`PluginManager::invoke_hook`](crate::PluginManager::invoke_hook).
+#[derive(Debug, Default)]
+pub struct HookReport {
+    /// The output of each subscriber, keyed by plugin name, in the order the
+    /// subscribers were called.
+    pub results: IndexMap<String, Result<Box<dyn Any + Send>, PluginError>>,
+}
+
+impl HookReport {
+    /// Returns `true` if every subscriber succeeded.
+    pub fn is_success(&self) -> bool {
+        self.results.values().all(Result::is_ok)
+    }
+
+    /// The subscribers that failed, and why, in the order they were called.
+    pub fn failures(&self) -> impl Iterator<Item = (&String, &PluginError)> {
+        self.results
+            .iter()
+            .filter_map(|(name, result)| result.as_ref().err().map(|error| (name, error)))
+    }
+
+    /// The outputs of type `T`, in the order the subscribers were called.
+    /// Failures and outputs of other types are left out.
+    pub fn outputs<T: Any>(&self) -> impl Iterator<Item = (&String, &T)> {
+        self.results.iter().filter_map(|(name, result)| {
+            let output = result.as_ref().ok()?.downcast_ref::<T>()?;
+            Some((name, output))
+        })
+    }
+}
@@ -17,13 +17,15 @@
 //!
 //! ## Creating Plugins
 //!
-//! To create a plugin, implement the `Plugin` trait and export a `create_plugins` function:
+//! To create a plugin, implement the `Plugin` trait and export it with the `declare_plugins!` macro.
+//! The macro generates the `create_plugins` function along with the `AbiDescriptor` the
+//! `PluginManager` checks before loading the library:
 //!
 //! The `as_any` method is required to allow access to the methods not
 //! mentioned in the `Plugin` trait, and needs to be set up to return self.
 //!
 //! ```rust
-//! use plugin_manager::Plugin;
+//! use plugin_manager::{Plugin, declare_plugins};
 //! use std::any::Any;
 //!
 //! #[derive(Debug)]
@@ -34,7 +36,7 @@
 //!         "my_plugin".to_string()
 //!     }
 //!
-//!     fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error>> {
+//!     fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 //!         println!("Executing MyPlugin");
 //!         Ok(())
 //!     }
@@ -44,10 +46,137 @@
 //!     }
 //! }
 //!
-//! #[unsafe(no_mangle)]
-//! pub fn create_plugins() -> Vec<Box<dyn Plugin>> {
-//!     vec![Box::new(MyPlugin)]
+//! declare_plugins!(MyPlugin);
+//! ```
+//!
+//! ### Deriving the Plugin trait
+//!
+//! With the default `derive` feature, `#[derive(Plugin)]` implements `as_any`, `name` and the
+//! metadata methods of the `Plugin` trait, leaving only `execute` to be written through the
+//! `Execute` trait. The name defaults to the type name in snake case.
+//!
+//! ```rust
+//! use plugin_manager::{Execute, Plugin, declare_plugins};
+//! use std::any::Any;
+//!
+//! #[derive(Debug, Plugin)]
+//! #[plugin(name = "my_plugin", group = "inventory", version = "1.2.0")]
+//! struct MyPlugin;
+//!
+//! impl Execute for MyPlugin {
+//!     fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!         println!("Executing MyPlugin");
+//!         Ok(())
+//!     }
+//! }
+//!
+//! declare_plugins!(MyPlugin);
+//! ```
+//!
+//! ### Configuring plugins
+//!
+//! A plugin receives the `config` table of its manifest entry through `Plugin::configure`
+//! before it is registered. With the derive, `#[plugin(configure)]` forwards the call to the
+//! `Configure` trait. The value can be deserialised into a typed struct with
+//! `config.clone().try_into()`, and returning an error fails the activation of the plugin.
+//!
+//! ```rust
+//! use plugin_manager::{Configure, Execute, Plugin, toml};
+//! use std::any::Any;
+//!
+//! #[derive(Debug, Default, Plugin)]
+//! #[plugin(name = "my_plugin", configure)]
+//! struct MyPlugin {
+//!     retries: u32,
+//! }
+//!
+//! impl Configure for MyPlugin {
+//!     fn configure(&mut self, config: &toml::Value) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!         self.retries = config.get("retries").and_then(toml::Value::as_integer).unwrap_or(1) as u32;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! impl Execute for MyPlugin {
+//!     fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!         println!("Executing MyPlugin with {} retries", self.retries);
+//!         Ok(())
+//!     }
+//! }
+//! ```
+//!
+//! ### Lifecycle hooks
+//!
+//! The `Plugin` trait has default-implemented hooks that the `PluginManager` calls as a plugin
+//! is registered and removed:
+//!
+//! - `on_load` runs before the plugin is registered, and an error aborts its registration.
+//! - `on_activate` runs once the plugin is registered.
+//! - `on_deactivate` and then `on_unload` run when the plugin is deregistered, its library is
+//!   unloaded or the `PluginManager` is dropped. An error from `on_unload` does not keep the
+//!   plugin registered; it is logged and returned by `PluginManager::take_lifecycle_errors`,
+//!   as are panics in `on_activate` and `on_deactivate`.
+//!
+//! With the derive, `#[plugin(lifecycle)]` forwards the hooks to the `Lifecycle` trait, whose
+//! methods all have defaults.
+//!
+//! ### Describing plugins
+//!
+//! A plugin can describe itself through `Plugin::description`, `authors`, `tags` and
+//! `capabilities`, or the matching `#[plugin(...)]` attributes. When it is registered the
+//! `PluginManager` stores these in a `PluginDescriptor`, along with its version, the path of
+//! its shared object and a SHA-256 hash of the library. `PluginManager::find_by_tag` and
+//! `PluginManager::find_by_capability` look plugins up by what they declare:
+//!
+//! ```rust
+//! use plugin_manager::{Execute, Plugin, PluginManager};
+//! use std::any::Any;
+//!
+//! #[derive(Debug, Plugin)]
+//! #[plugin(description = "Exports rows as CSV", tags("export"), capabilities("export-csv"))]
+//! struct CsvExporter;
+//!
+//! impl Execute for CsvExporter {
+//!     fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let mut plugin_manager = PluginManager::new();
+//! plugin_manager.register_plugin(Box::new(CsvExporter), None).unwrap();
+//! let exporters = plugin_manager.find_by_capability("export-csv");
+//! assert_eq!(exporters[0].descriptor.name, "csv_exporter");
+//! ```
+//!
+//! ### Typed plugins
+//!
+//! `Plugin::execute` takes a `&dyn Any` and returns nothing. A plugin that implements
+//! `TypedPlugin` instead declares the type of its context and of its output, and
+//! `#[plugin(typed)]` adapts it to the `Plugin` trait. `PluginManager::execute_typed` then
+//! returns the output, or a `PluginError::ContextMismatch` when the context has another type:
+//!
+//! ```rust
+//! use plugin_manager::{Plugin, PluginManager, TypedPlugin};
+//!
+//! #[derive(Debug, Plugin)]
+//! #[plugin(name = "word_count", typed)]
+//! struct WordCount;
+//!
+//! impl TypedPlugin for WordCount {
+//!     type Context = String;
+//!     type Output = usize;
+//!
+//!     fn run(&self, context: &String) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+//!         Ok(context.split_whitespace().count())
+//!     }
 //! }
+//!
+//! let mut plugin_manager = PluginManager::new();
+//! plugin_manager.register_plugin(Box::new(WordCount), None).unwrap();
+//! let count: usize = plugin_manager
+//!     .execute_typed("word_count", &"one two three".to_string())
+//!     .unwrap();
+//! assert_eq!(count, 3);
 //! ```
 //!
 //! ## Setting up Cargo.toml for Plugins
@@ -177,6 +306,91 @@
 //! plugin_c = "/path/to/plugin_c.so"
 //! ```
 //!
+//! Relative paths are resolved against the directory of the manifest that declares them,
+//! so activation does not depend on the working directory. Paths may also start with `~`
+//! and reference environment variables as `${ENV_VAR}`.
+//!
+//! Since library file names differ between platforms, a plugin can also be referenced by
+//! the crate that builds it. The path is expanded with the platform's library prefix and
+//! extension, so the same manifest works on Linux, macOS and Windows:
+//!
+//! ```toml
+//! [package.metadata.plugins]
+//! plugin_a = { crate = "plugin_a", profile = "release", target_dir = "../target" }
+//! ```
+//!
+//! Entries can also be written as a table to disable a plugin without removing it, to
+//! activate it before others with a higher `priority`, or to give it its own settings:
+//!
+//! ```toml
+//! [package.metadata.plugins]
+//! plugin_a = { path = "/path/to/plugin_a.so", enabled = false }
+//! plugin_b = { path = "/path/to/plugin_b.so", group = "inventory", priority = 10, config = { retries = 3 } }
+//! ```
+//!
+//! A table with a `path` whose keys are all settings like the above, such as
+//! `plugin_a = { path = "/path/to/plugin_a.so" }`, is read as an entry. Any other table of
+//! paths is a group, so a group can still contain a plugin named `path` alongside others.
+//!
+//! A table entry can name the plugins that must be registered before its own with `depends_on`,
+//! as can a plugin through `Plugin::depends_on` or `#[plugin(depends_on("task_a"))]`. Plugins are activated
+//! after their dependencies, and activation fails on a missing dependency or a cycle. The plugins
+//! of a cycle fail with `PluginError::DependencyCycle`, and those depending on them with
+//! `PluginError::MissingDependency`. Dependencies name plugins, so a plugin renamed under
+//! `ConflictPolicy::RenameWithGroupPrefix` still satisfies them. A plugin that others depend on
+//! is only deregistered with `PluginManager::force_deregister_plugin`.
+//!
+//! ```toml
+//! [package.metadata.plugins]
+//! inventory = { path = "/path/to/libplugin_inventory.so", depends_on = ["task_a"] }
+//! ```
+//!
+//! Plugins are registered by descending `priority`, then in the order they appear in the
+//! metadata, with each plugin after its dependencies. Every listing, such as
+//! `PluginManager::get_all_plugin_names`, follows the registration order.
+//!
+//! A table entry can also require a semver `version`, which the version reported by each of
+//! its plugins through `Plugin::version` or `#[plugin(version = "...")]` must satisfy:
+//!
+//! ```toml
+//! [package.metadata.plugins]
+//! plugin_a = { path = "/path/to/plugin_a.so", version = "^1.2" }
+//! ```
+//!
+//! Instead of listing every library, directories can be scanned for files named like a
+//! dynamic library (`lib*.so`, `*.dll`, `lib*.dylib`). A directory is either a path or a
+//! table with `recursive`, a glob `pattern` on the file name and a `group`:
+//!
+//! ```toml
+//! [package.metadata]
+//! plugin_dirs = [
+//!     "./plugins",
+//!     { path = "./inventory", recursive = true, pattern = "*inventory*", group = "inventory" },
+//! ]
+//! ```
+//!
+//! Directories can also be added with `PluginManager::with_plugin_dir`. Recursive scans do not
+//! follow symlinked directories.
+//!
+//! By default the metadata is read from the manifest named by `CARGO_MANIFEST_PATH`, which
+//! is only set when running under cargo; without it, reading the metadata fails with a
+//! `PluginError::Metadata` saying so. Deployed binaries can read it from another
+//! `PluginConfigSource` instead: an explicit file, a `plugins.toml` in the working directory
+//! or next to the executable, or a string. Standalone files keep the metadata at the top level:
+//!
+//! ```toml
+//! [plugins]
+//! plugin_a = "/path/to/plugin_a.so"
+//! ```
+//!
+//! ```rust,ignore
+//! use plugin_manager::{PluginConfigSource, PluginManager};
+//!
+//! let plugin_manager = PluginManager::new()
+//!     .with_config_source(PluginConfigSource::PluginsToml)
+//!     .activate_plugins()?;
+//! ```
+//!
 //! ## Usage
 //!
 //! Here's a basic example of how to use the `PluginManager`:
@@ -187,7 +401,7 @@
 //! # }
 //! use plugin_manager::PluginManager;
 //!
-//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 //!     // Create a new PluginManager
 //!     let mut plugin_manager = PluginManager::new();
 //!
@@ -198,8 +412,8 @@
 //!     plugin_manager.execute_plugin("plugin_a", &())?;
 //!
 //!     // Deregister a plugin
-//!     let deregistered = plugin_manager.deregister_plugin("plugin_b");
-//!     print!("Deregistered plugin: {:?}", deregistered);
+//!     let deregistered = plugin_manager.deregister_plugin("plugin_b")?;
+//!     println!("Deregistered plugin: {}", deregistered);
 //!
 //!     // Deregister all plugins
 //!     let deregistered = plugin_manager.deregister_all_plugins();
@@ -209,47 +423,392 @@
 //! }
 //! ```
 //!
+//! ### Panics
+//!
+//! Panics in plugin code, whether in `create_plugins`, `configure`, a lifecycle hook or
+//! `execute`, are caught and returned as `PluginError::PluginPanicked` instead of unwinding
+//! into the host. A panic cannot unwind out of a plugin library, so `declare_plugins!` and
+//! `#[derive(Plugin)]` catch it within the library; a hand-written `Plugin` implementation in
+//! a library must not panic. With `PluginManager::with_panic_quarantine(true)`, a plugin that
+//! panics while executing is quarantined: later calls return `PluginError::Quarantined` until
+//! `PluginManager::reset_health` is called.
+//!
+//! ### Timeouts and cancellation
+//!
+//! `PluginManager::execute_plugin_with_timeout` runs a plugin on a worker thread and returns
+//! `PluginError::Timeout` if it has not finished in time. The plugin cannot be interrupted, so
+//! the manager cancels the `CancellationToken` passed to `Plugin::execute_cancellable` and marks
+//! the plugin `PluginHealth::Unhealthy` until `PluginManager::reset_health` is called. A plugin
+//! derived with `#[plugin(cancellable)]` implements the `Cancellable` trait and checks
+//! `token.is_cancelled()` to stop early. `PluginManager::execute_plugin_cancellable` passes a
+//! token of your own.
+//!
+//! ### Executing a group
+//!
+//! `PluginManager::execute_group` executes every plugin of a group with the same context and
+//! returns a `GroupReport` holding each plugin's result under its name, in registration order.
+//! Each call chooses how the plugins run: one after the other with `ExecutionMode::Sequential`,
+//! or on a pool of worker threads with `ExecutionMode::Parallel { threads: 4 }`. The context is
+//! shared between the threads, so it must be `Sync`. With `FailurePolicy::FailFast` no further
+//! plugins are started once one fails, and those left out are listed in `GroupReport::skipped`;
+//! `FailurePolicy::CollectAll` runs them all.
+//!
+//! ```rust,ignore
+//! let plugin_manager = PluginManager::new().activate_plugins()?;
+//! let report = plugin_manager.execute_group(
+//!     "inventory",
+//!     &"export",
+//!     ExecutionMode::Parallel { threads: 4 },
+//!     FailurePolicy::CollectAll,
+//! );
+//! for (name, error) in report.failures() {
+//!     eprintln!("{name}: {error}");
+//! }
+//! ```
+//!
+//! ### Pipelines
+//!
+//! `PluginManager::pipeline` chains plugins so that each stage is executed with the output of
+//! the one before it, through `Plugin::execute_any`. Typed plugins make the outputs meaningful:
+//!
+//! ```rust,ignore
+//! let export: String = plugin_manager
+//!     .pipeline("inventory")
+//!     .then("transform")
+//!     .then("export")
+//!     .run(InventoryQuery::default())?;
+//! ```
+//!
+//! The pipeline stops at the first stage that fails and returns a `PipelineError` with the
+//! stage's position, its plugin name, the input it was executed with and the `PluginError`.
+//!
+//! ### Extension points
+//!
+//! The host declares named extension points with `PluginManager::with_hook`, and plugins
+//! subscribe to them through `Plugin::hooks`, each with a priority. `PluginManager::invoke_hook`
+//! calls every subscriber by descending priority through `Plugin::handle_hook`, which defaults
+//! to `execute_any`, and returns a `HookReport` holding each subscriber's output or error under
+//! its name. A plugin derived with `#[plugin(hooks)]` implements the `Hooks` trait instead:
+//!
+//! ```rust,ignore
+//! #[derive(Debug, Plugin)]
+//! #[plugin(hooks)]
+//! struct Validator;
+//!
+//! impl Hooks for Validator {
+//!     fn hooks(&self) -> Vec<HookSubscription> {
+//!         vec![HookSubscription::new("before_save", 10)]
+//!     }
+//! }
+//!
+//! let plugin_manager = PluginManager::new().with_hook("before_save").activate_plugins()?;
+//! let report = plugin_manager.invoke_hook("before_save", &document)?;
+//! ```
+//!
 //!
 //! This module provides a robust foundation for building plugin-based architectures
 //! in Rust applications, offering flexibility and ease of use.
 
+// Lets `#[derive(Plugin)]` refer to `::plugin_manager` from within this crate.
+extern crate self as plugin_manager;
+
+mod abi;
+mod cancel;
+mod config;
+mod descriptor;
+mod discovery;
+mod error;
+mod group;
+mod hook;
+mod panic;
+mod pipeline;
+mod typed;
+
+pub use abi::{ABI_DESCRIPTOR_SYMBOL, AbiDescriptor, CREATE_PLUGINS_SYMBOL, PLUGIN_ABI_VERSION};
+pub use cancel::CancellationToken;
+pub use config::{CrateReference, PLUGINS_TOML, PluginConfigSource};
+pub use descriptor::PluginDescriptor;
+pub use discovery::PluginDir;
+pub use error::PluginError;
+pub use group::{ExecutionMode, FailurePolicy, GroupReport};
+pub use hook::{HookReport, HookSubscription};
+/// The ordered map of the plugin registry and metadata.
+pub use indexmap::IndexMap;
+pub use pipeline::{Pipeline, PipelineError};
+#[cfg(feature = "derive")]
+pub use plugin_manager_derive::Plugin;
+/// The `semver` crate used for plugin versions and requirements.
+pub use semver;
+/// The `toml` crate used for plugin settings, so plugins build against the same version.
+pub use toml;
+pub use typed::TypedPlugin;
+
+/// Items used by the code generated by [`declare_plugins!`] and
+/// `#[derive(Plugin)]`. Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::panic::{catch_message, catch_plugin_panic};
+}
+
 use libloading::{Library, Symbol};
+use semver::VersionReq;
 use serde::Deserialize;
 use std::any::Any;
-use std::collections::{HashMap, hash_map};
-use std::io::{Error, ErrorKind};
-use std::mem::ManuallyDrop;
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
 
 type PathString = String;
 type GroupOrName = String;
-type PluginResult = Result<(Library, Vec<Box<dyn Plugin>>), Box<dyn std::error::Error>>;
+type PluginResult = Result<(Library, Vec<Box<dyn Plugin>>), PluginError>;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
 pub struct Metadata {
-    pub plugins: Option<HashMap<GroupOrName, PluginEntry>>,
+    pub plugins: Option<IndexMap<GroupOrName, PluginEntry>>,
+    /// Directories scanned for plugin libraries.
+    pub plugin_dirs: Option<Vec<PluginDir>>,
 }
 
-/// Information about a plugin entry. This can either be a single plugin
-/// or a group of plugins.
-#[derive(Deserialize, Debug, Clone)]
-#[serde(untagged)]
+/// Information about a plugin entry. This can either be a single plugin,
+/// a single plugin with settings, or a group of plugins.
+///
+/// Each path may also be given as a [`CrateReference`], which is expanded
+/// into the platform-specific path of the library when deserialized. A table
+/// is a [`Detailed`](PluginEntry::Detailed) entry when it has a `path` and
+/// all of its keys are fields of [`PluginSpec`], and otherwise a
+/// [`Group`](PluginEntry::Group), so a group may contain a plugin named
+/// `path` alongside others.
+#[derive(Debug, Clone)]
 pub enum PluginEntry {
     Individual(PathString),
-    Group(HashMap<String, PathString>),
+    Detailed(PluginSpec),
+    Group(IndexMap<String, PathString>),
+}
+
+impl PluginEntry {
+    /// Whether the entry should be loaded. Only the table form can be disabled.
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            PluginEntry::Detailed(spec) => spec.enabled,
+            _ => true,
+        }
+    }
+
+    /// The activation priority of the entry. Entries with a higher priority
+    /// are activated first, and default to `0`.
+    pub fn priority(&self) -> i32 {
+        match self {
+            PluginEntry::Detailed(spec) => spec.priority,
+            _ => 0,
+        }
+    }
+}
+
+/// The table form of a plugin entry:
+///
+/// ```toml
+/// [package.metadata.plugins]
+/// plugin_a = { path = "/path/to/plugin_a.so", enabled = false, priority = 10, config = { retries = 3 } }
+/// ```
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PluginSpec {
+    /// The path of the library, or a [`CrateReference`].
+    #[serde(deserialize_with = "config::path_or_crate")]
+    pub path: PathString,
+    /// The group the plugins of the library are registered under.
+    pub group: Option<String>,
+    /// Disabled entries are skipped when activating plugins.
+    #[serde(default = "PluginSpec::default_enabled")]
+    pub enabled: bool,
+    /// Entries with a higher priority are activated first.
+    #[serde(default)]
+    pub priority: i32,
+    /// Settings for the plugins of the library.
+    #[serde(default)]
+    pub config: Option<toml::Table>,
+    /// The names of the plugins that must be registered before the plugins
+    /// of the library.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// The semver requirement the versions of the plugins must satisfy.
+    pub version: Option<VersionReq>,
+}
+
+impl PluginSpec {
+    fn default_enabled() -> bool {
+        true
+    }
 }
 
 /// Information about a loaded plugin, including the plugin itself and its group.
+///
+/// Plugins loaded from a shared object keep a reference to its [`Library`],
+/// which is closed once the last plugin created from it is dropped. The
+/// plugin is declared first so it is always dropped before the library.
 pub struct PluginInfo {
-    pub plugin: Box<dyn Plugin>,
+    /// The plugin, shared with the worker threads of
+    /// [`PluginManager::execute_plugin_with_timeout`].
+    pub plugin: Arc<dyn Plugin>,
+    pub group: Option<String>,
+    /// The metadata of the plugin, and the shared object it was loaded from.
+    pub descriptor: PluginDescriptor,
+    /// The plugins this plugin depends on, from [`Plugin::depends_on`] and
+    /// its manifest entry.
+    pub depends_on: Vec<String>,
+    health: AtomicU8,
+    // Keeps the shared object loaded while the plugin is alive.
+    library: Option<Arc<Library>>,
+}
+
+impl PluginInfo {
+    /// The health of the plugin, as last recorded by the manager.
+    pub fn health(&self) -> PluginHealth {
+        PluginHealth::from_u8(self.health.load(Ordering::Relaxed))
+    }
+
+    /// Whether the plugin panicked and is no longer executed; see
+    /// [`PluginManager::with_panic_quarantine`].
+    pub fn is_quarantined(&self) -> bool {
+        self.health() == PluginHealth::Quarantined
+    }
+
+    fn set_health(&self, health: PluginHealth) {
+        self.health.store(health as u8, Ordering::Relaxed);
+    }
+}
+
+/// The health of a registered plugin.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PluginHealth {
+    #[default]
+    Healthy,
+    /// The plugin did not finish within the timeout of
+    /// [`PluginManager::execute_plugin_with_timeout`]. It can still be executed.
+    Unhealthy,
+    /// The plugin panicked and is not executed until its health is reset with
+    /// [`PluginManager::reset_health`].
+    Quarantined,
+}
+
+impl PluginHealth {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => PluginHealth::Unhealthy,
+            2 => PluginHealth::Quarantined,
+            _ => PluginHealth::Healthy,
+        }
+    }
+}
+
+/// A plugin entry that could not be activated.
+#[derive(Debug)]
+pub struct ActivationFailure {
+    /// The name of the entry in the plugin metadata, or the path of a plugin
+    /// directory that could not be scanned.
+    pub entry: String,
     pub group: Option<String>,
+    pub path: PathString,
+    pub error: PluginError,
+}
+
+/// The outcome of [`PluginManager::activate_available_plugins`].
+#[derive(Debug, Default)]
+pub struct ActivationReport {
+    /// The names the plugins were registered under, which differ from their
+    /// own names under [`ConflictPolicy::RenameWithGroupPrefix`].
+    pub activated: Vec<String>,
+    /// The entries that failed to load or register, and why.
+    pub failures: Vec<ActivationFailure>,
+}
+
+impl ActivationReport {
+    /// Records a failed entry, or returns the error in `strict` mode.
+    fn fail(
+        &mut self,
+        strict: bool,
+        library: &EntryLibrary,
+        error: PluginError,
+    ) -> Result<(), PluginError> {
+        self.record(
+            strict,
+            ActivationFailure {
+                entry: library.entry.to_string(),
+                group: library.group.clone(),
+                path: library.path.to_string(),
+                error,
+            },
+        )
+    }
+
+    /// Records a plugin directory that could not be scanned, or returns the
+    /// error in `strict` mode. The directory stands in for the entry name.
+    fn fail_dir(
+        &mut self,
+        strict: bool,
+        plugin_dir: &PluginDir,
+        error: PluginError,
+    ) -> Result<(), PluginError> {
+        self.record(
+            strict,
+            ActivationFailure {
+                entry: plugin_dir.path.clone(),
+                group: plugin_dir.group.clone(),
+                path: plugin_dir.path.clone(),
+                error,
+            },
+        )
+    }
+
+    fn record(&mut self, strict: bool, failure: ActivationFailure) -> Result<(), PluginError> {
+        if strict {
+            return Err(failure.error);
+        }
+        self.failures.push(failure);
+        Ok(())
+    }
+
+    /// Returns `true` if every entry was activated.
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Decides what happens when a plugin is registered under a name that is
+/// already taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Reject the new plugin with [`PluginError::DuplicatePlugin`].
+    #[default]
+    Error,
+    /// Keep the plugin that was registered first and discard the new one.
+    KeepFirst,
+    /// Replace the registered plugin with the new one.
+    ReplaceExisting,
+    /// Register the new plugin as `group::name`. Plugins without a group, or
+    /// whose prefixed name is also taken, are rejected.
+    RenameWithGroupPrefix,
 }
 
 /// Manages the lifecycle of loaded plugins.
+///
+/// Plugins are kept in the order they were registered, which every listing
+/// follows. Activation registers them by descending entry priority, then in
+/// the order of the metadata, with each plugin after its dependencies.
 pub struct PluginManager {
-    pub plugins: HashMap<String, PluginInfo>,
+    pub plugins: IndexMap<String, PluginInfo>,
     // plugin_path: Vec<String>
-    plugin_path: Vec<HashMap<GroupOrName, PluginEntry>>,
+    plugin_path: Vec<IndexMap<GroupOrName, PluginEntry>>,
+    plugin_dirs: Vec<PluginDir>,
+    config_source: PluginConfigSource,
+    conflict_policy: ConflictPolicy,
+    lifecycle_errors: Vec<PluginError>,
+    quarantine_on_panic: bool,
+    hook_points: Vec<String>,
 }
 
 pub trait Plugin: Send + Sync + Any {
@@ -265,437 +824,3114 @@ pub trait Plugin: Send + Sync + Any {
     ///
     /// If the plugin has other methods, they can be accessed through
     /// the `as_any` method.
-    fn execute(&self, context: &dyn Any) -> Result<(), Box<dyn std::error::Error>>;
-}
+    fn execute(&self, context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 
-type PluginCreate = unsafe fn() -> Vec<Box<dyn Plugin>>;
+    /// Executes the plugin and returns its output, for
+    /// [`PluginManager::execute_typed`]. Defaults to [`execute`](Plugin::execute),
+    /// returning `()`.
+    ///
+    /// Plugins implementing [`TypedPlugin`] forward this to
+    /// [`TypedPlugin::run_any`].
+    fn execute_any(
+        &self,
+        context: &dyn Any,
+    ) -> Result<Box<dyn Any + Send>, Box<dyn std::error::Error + Send + Sync>> {
+        self.execute(context)
+            .map(|()| Box::new(()) as Box<dyn Any + Send>)
+    }
 
-impl Default for PluginManager {
-    fn default() -> Self {
-        Self::new()
+    /// Executes the plugin with a [`CancellationToken`] it can check to stop
+    /// early, for [`PluginManager::execute_plugin_with_timeout`] and
+    /// [`PluginManager::execute_plugin_cancellable`]. Defaults to
+    /// [`execute`](Plugin::execute), ignoring the token.
+    fn execute_cancellable(
+        &self,
+        context: &dyn Any,
+        _token: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.execute(context)
     }
-}
 
-impl PluginManager {
-    pub fn new() -> Self {
-        PluginManager {
-            plugins: HashMap::new(),
-            plugin_path: Vec::new(),
-        }
+    /// The group the plugin belongs to when its manifest entry, or the call
+    /// to [`PluginManager::register_plugin`], does not set one.
+    fn group(&self) -> Option<String> {
+        None
     }
 
-    pub fn activate_plugins(mut self) -> Result<PluginManager, Box<dyn std::error::Error>> {
-        let meta_data = self.get_plugin_metadata();
-        log::debug!("Plugin metadata: {:?}", meta_data);
-        let mut registrations = Vec::new();
-        if let Some(plugin_config) = meta_data.plugins {
-            for (group_or_name, plugin_entry) in plugin_config {
-                registrations.push((group_or_name, plugin_entry));
-            }
-        } else {
-            log::error!("No plugin metadata found in manifest");
-            return Err("No plugin metadata found in manifest".into());
-        }
-        if !self.plugin_path.is_empty() {
-            for entry in &self.plugin_path {
-                for (group_or_name, plugin_entry) in entry {
-                    registrations.push((group_or_name.clone(), plugin_entry.clone()));
-                }
-            }
-        }
-        for (group_or_name, plugin_entry) in registrations {
-            self.activation_registration(group_or_name.clone(), &plugin_entry)?;
-        }
-        Ok(self)
+    /// The semver version of the plugin, checked against the `version`
+    /// requirement of its manifest entry.
+    fn version(&self) -> Option<String> {
+        None
     }
 
-    fn activation_registration(
-        &mut self,
-        group_or_name: String,
-        plugin_entry: &PluginEntry,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        match plugin_entry {
-            PluginEntry::Individual(path) => {
-                log::debug!("Loading individual plugin: {group_or_name} {path}");
-                let (library, plugins) = self.load_plugin(path)?;
-                self.register_plugins_vec(plugins, None);
-                let _library = ManuallyDrop::new(library);
-            }
-            PluginEntry::Group(group_plugins) => {
-                group_plugins.iter().for_each(|(name, path)| {
-                    log::debug!("Loading plugin group: {group_or_name}, {name} {path}");
-                    let (library, plugins) = self.load_plugin(path).unwrap();
-                    self.register_plugins_vec(plugins, Some(group_or_name.clone()));
-                    let _library = ManuallyDrop::new(library);
-                });
-            }
-        }
-        Ok(())
+    /// A short description of the plugin.
+    fn description(&self) -> Option<String> {
+        None
     }
 
-    /// Registers each plugin by the name returned by the plugin's `name` method.
-    /// It allows for plugins to be grouped together for easier management within
-    /// a single crated if there share similar traits.
-    pub fn register_plugin(&mut self, plugin: Box<dyn Plugin>, group: Option<String>) {
-        log::info!("Registering plugin: {:?}", plugin.name());
-        let name = plugin.name().to_string();
-        let plugin_info = PluginInfo { plugin, group };
+    /// The authors of the plugin.
+    fn authors(&self) -> Vec<String> {
+        Vec::new()
+    }
 
-        if let hash_map::Entry::Vacant(entry) = self.plugins.entry(name.clone()) {
-            entry.insert(plugin_info);
-        } else {
-            let msg = format!("Plugin '{}' already registered", &name);
-            log::error!("{msg}");
-            panic!("{msg}");
-        }
+    /// Free-form labels used to find the plugin with [`PluginManager::find_by_tag`].
+    fn tags(&self) -> Vec<String> {
+        Vec::new()
     }
 
-    /// Deregisters the plugin with the given name.
-    pub fn deregister_plugin(&mut self, name: &str) -> Option<String> {
-        log::info!("De-registering plugin: {}", name);
-        let plugin = self.plugins.remove(name);
+    /// What the plugin can do, such as `"export-csv"`, used to find it with
+    /// [`PluginManager::find_by_capability`].
+    fn capabilities(&self) -> Vec<String> {
+        Vec::new()
+    }
 
-        match plugin {
-            None => None,
-            Some(plugin_info) => Some(plugin_info.plugin.name()),
-        }
+    /// The names of the plugins that must be registered before this one.
+    fn depends_on(&self) -> Vec<String> {
+        Vec::new()
     }
 
-    pub fn deregister_all_plugins(&mut self) -> Vec<String> {
-        let names: Vec<String> = self.plugins.drain().map(|(name, _)| name).collect();
-        names.iter().for_each(|name| {
-            log::info!("De-registered plugin: {}", name);
-        });
-        names
+    /// The extension points the plugin subscribes to, called with
+    /// [`handle_hook`](Plugin::handle_hook) by [`PluginManager::invoke_hook`].
+    fn hooks(&self) -> Vec<HookSubscription> {
+        Vec::new()
     }
-    /// Loops over the plugins and registers them to the plugin manager
-    fn register_plugins_vec(&mut self, plugins: Vec<Box<dyn Plugin>>, group: Option<String>) {
-        for plugin in plugins {
-            self.register_plugin(plugin, group.clone());
-        }
+
+    /// Handles an extension point the plugin subscribes to. Defaults to
+    /// [`execute_any`](Plugin::execute_any).
+    fn handle_hook(
+        &self,
+        _point: &str,
+        context: &dyn Any,
+    ) -> Result<Box<dyn Any + Send>, Box<dyn std::error::Error + Send + Sync>> {
+        self.execute_any(context)
     }
 
-    /// Loads a plugin from a shared object file and registers it to the plugin manager.
-    pub fn load_plugin(&self, filename: &str) -> PluginResult {
-        let path = Path::new(filename);
+    /// Receives the `config` table of the plugin's manifest entry before the
+    /// plugin is registered. It is not called for entries without one.
+    ///
+    /// Returning an error fails the activation of the plugin.
+    fn configure(
+        &mut self,
+        _config: &toml::Value,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
 
-        if !path.exists() {
-            let msg = format!("Plugin file does not exist: {}", filename);
-            log::error!("{msg}");
-            return Err(msg.into());
-        } else {
-            log::debug!("Attempting to load plugin: {}", filename);
-        }
+    /// Called before the plugin is registered, after [`configure`](Plugin::configure).
+    ///
+    /// Returning an error aborts the registration of the plugin.
+    fn on_load(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
 
-        let library = unsafe { Library::new(path)? };
-        log::debug!("Library loaded successfully");
+    /// Called once the plugin has been registered.
+    fn on_activate(&self) {}
 
-        let create_plugin: Symbol<PluginCreate> = unsafe { library.get(b"create_plugins")? };
-        log::debug!("Found create_plugins symbol");
+    /// Called when the plugin is deregistered, before [`on_unload`](Plugin::on_unload).
+    fn on_deactivate(&self) {}
 
-        let plugins = unsafe { create_plugin() };
-        log::debug!("Plugin created successfully");
+    /// Called when the plugin is deregistered, before it is dropped.
+    ///
+    /// An error does not keep the plugin registered. It is logged and can be
+    /// retrieved with [`PluginManager::take_lifecycle_errors`].
+    fn on_unload(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
 
-        Ok((library, plugins))
+    /// Runs [`hooks`](Plugin::hooks), returning a panic caught within the
+    /// plugin's library as an error. Not meant to be overridden.
+    #[doc(hidden)]
+    fn try_hooks(&self) -> Result<Vec<HookSubscription>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.hooks())
     }
 
-    /// Retrieves the environment variable CARGO_MANIFEST_PATH containing the
-    /// path to  manifest file. The file should contain the plugin metadata
-    /// in TOML format which contains the following structure:
-    ///
-    /// ```toml
-    /// [package.metadata.plugins]
-    /// plugin_a = "/path/to/plugin_a.so"
-    ///
-    /// [package.metadata.plugins.inventory]
-    /// inventory_plugin = "/path/to/inventory_plugin.so"
-    /// ```
-    pub fn get_plugin_metadata(&self) -> Metadata {
-        let plugin_a_path =
-            std::env::var("CARGO_MANIFEST_PATH").unwrap_or_else(|_| ".".to_string());
+    /// Runs [`on_activate`](Plugin::on_activate), returning a panic caught
+    /// within the plugin's library as an error. Not meant to be overridden.
+    #[doc(hidden)]
+    fn try_on_activate(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.on_activate();
+        Ok(())
+    }
 
-        let file_string = std::fs::read_to_string(plugin_a_path);
-        let manifest = match file_string {
-            Ok(manifest) => manifest,
-            Err(msg) => {
-                eprintln!("Error reading manifest file {}", msg);
-                return Metadata { plugins: None };
-            }
-        };
-        let value: toml::Value = toml::from_str(&manifest).unwrap();
-        // let metadata = if let Some(meta_data) = value
-        if let Some(meta_data) = value
-            .get("package")
-            .and_then(|p| p.get("metadata"))
-            .and_then(|m| m.as_table())
+    /// Runs [`on_deactivate`](Plugin::on_deactivate), returning a panic caught
+    /// within the plugin's library as an error. Not meant to be overridden.
+    #[doc(hidden)]
+    fn try_on_deactivate(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.on_deactivate();
+        Ok(())
+    }
+
+    /// Rebuilds a [`PluginError::PluginPanicked`] or
+    /// [`PluginError::ContextMismatch`] returned by one of the plugin's methods
+    /// as a `PluginError` of the host.
+    ///
+    /// A plugin library has its own copy of `PluginError`, which the host
+    /// cannot downcast to, so the host asks the plugin, whose code lives in
+    /// that library. Not meant to be overridden.
+    #[doc(hidden)]
+    fn plugin_error(
+        &self,
+        error: &(dyn std::error::Error + Send + Sync + 'static),
+    ) -> Option<PluginError> {
+        match error.downcast_ref::<PluginError>()? {
+            PluginError::PluginPanicked { name, message } => Some(PluginError::PluginPanicked {
+                name: name.clone(),
+                message: message.clone(),
+            }),
+            PluginError::ContextMismatch { name, expected } => Some(PluginError::ContextMismatch {
+                name: name.clone(),
+                expected: expected.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The `execute` half of a plugin whose [`Plugin`] implementation is derived.
+///
+/// `#[derive(Plugin)]` implements every other method of [`Plugin`] and
+/// forwards [`Plugin::execute`] to this trait:
+///
+/// ```rust
+/// use plugin_manager::{Execute, Plugin};
+/// use std::any::Any;
+///
+/// #[derive(Debug, Plugin)]
+/// #[plugin(name = "plugin_a", group = "inventory", version = "1.2.0")]
+/// struct PluginA;
+///
+/// impl Execute for PluginA {
+///     fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+///         println!("Executing Plugin A");
+///         Ok(())
+///     }
+/// }
+///
+/// assert_eq!(PluginA.name(), "plugin_a");
+/// assert_eq!(PluginA.group().as_deref(), Some("inventory"));
+/// ```
+pub trait Execute {
+    /// Executes a single function with the provided context.
+    fn execute(&self, context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// The `execute` half of a plugin whose [`Plugin`] implementation is derived
+/// with `#[plugin(cancellable)]`, which forwards [`Plugin::execute`] and
+/// [`Plugin::execute_cancellable`] to this trait. A plain `execute` gets a
+/// token that is never cancelled.
+pub trait Cancellable {
+    /// Executes the plugin, stopping early once `token` is cancelled.
+    fn execute_cancellable(
+        &self,
+        context: &dyn Any,
+        token: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// The `configure` half of a plugin whose [`Plugin`] implementation is derived
+/// with `#[plugin(configure)]`, which forwards [`Plugin::configure`] to this trait.
+pub trait Configure {
+    /// Applies the `config` table of the plugin's manifest entry.
+    fn configure(
+        &mut self,
+        config: &toml::Value,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// The lifecycle hooks of a plugin whose [`Plugin`] implementation is derived
+/// with `#[plugin(lifecycle)]`, which forwards the hooks of [`Plugin`] to this
+/// trait. Every hook has the same default as on [`Plugin`].
+pub trait Lifecycle {
+    /// See [`Plugin::on_load`].
+    fn on_load(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    /// See [`Plugin::on_activate`].
+    fn on_activate(&self) {}
+
+    /// See [`Plugin::on_deactivate`].
+    fn on_deactivate(&self) {}
+
+    /// See [`Plugin::on_unload`].
+    fn on_unload(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+/// The extension points of a plugin whose [`Plugin`] implementation is derived
+/// with `#[plugin(hooks)]`, which forwards [`Plugin::hooks`] and
+/// [`Plugin::handle_hook`] to this trait.
+pub trait Hooks {
+    /// See [`Plugin::hooks`].
+    fn hooks(&self) -> Vec<HookSubscription>;
+
+    /// See [`Plugin::handle_hook`].
+    fn handle_hook(
+        &self,
+        _point: &str,
+        context: &dyn Any,
+    ) -> Result<Box<dyn Any + Send>, Box<dyn std::error::Error + Send + Sync>>
+    where
+        Self: Plugin + Sized,
+    {
+        self.execute_any(context)
+    }
+}
+
+/// The plugins of the first dependency cycle in `graph`, which maps each
+/// plugin to the unregistered plugins it depends on, in the order of `graph`.
+fn dependency_cycle(graph: &IndexMap<String, Vec<String>>) -> Vec<String> {
+    let reachable = |from: &String| {
+        let mut seen = HashSet::new();
+        let mut stack: Vec<&String> = graph[from].iter().collect();
+        while let Some(name) = stack.pop() {
+            if seen.insert(name) {
+                stack.extend(graph.get(name).into_iter().flatten());
+            }
+        }
+        seen
+    };
+    graph
+        .keys()
+        .find_map(|name| {
+            let downstream = reachable(name);
+            downstream.contains(name).then(|| {
+                graph
+                    .keys()
+                    .filter(|other| downstream.contains(other) && reachable(other).contains(name))
+                    .cloned()
+                    .collect()
+            })
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the panic message if creating the plugins panicked.
+type PluginCreate = unsafe fn() -> Result<Vec<Box<dyn Plugin>>, String>;
+
+/// Deregisters the remaining plugins, so their `on_deactivate` and
+/// `on_unload` hooks run before their libraries are closed.
+impl Drop for PluginManager {
+    fn drop(&mut self) {
+        self.deregister_all_plugins();
+    }
+}
+
+impl Default for PluginManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A shared object listed by a plugin entry, with the settings of the entry,
+/// as produced by `entry_libraries` and loaded by `activate`.
+struct EntryLibrary<'a> {
+    entry: &'a str,
+    group: Option<String>,
+    path: &'a str,
+    config: Option<&'a toml::Table>,
+    depends_on: &'a [String],
+    version: Option<&'a VersionReq>,
+}
+
+impl PluginManager {
+    pub fn new() -> Self {
+        PluginManager {
+            plugins: IndexMap::new(),
+            plugin_path: Vec::new(),
+            plugin_dirs: Vec::new(),
+            config_source: PluginConfigSource::default(),
+            conflict_policy: ConflictPolicy::default(),
+            lifecycle_errors: Vec::new(),
+            quarantine_on_panic: false,
+            hook_points: Vec::new(),
+        }
+    }
+
+    /// Sets where the plugin metadata is read from. Defaults to
+    /// [`PluginConfigSource::CargoManifest`].
+    pub fn with_config_source(mut self, source: PluginConfigSource) -> Self {
+        self.config_source = source;
+        self
+    }
+
+    /// Quarantines a plugin once it panics while executing, so that it is not
+    /// executed again until [`reset_health`](Self::reset_health) is called.
+    /// Disabled by default.
+    pub fn with_panic_quarantine(mut self, quarantine: bool) -> Self {
+        self.quarantine_on_panic = quarantine;
+        self
+    }
+
+    /// Sets the policy used when a plugin name is already registered.
+    pub fn with_conflict_policy(mut self, policy: ConflictPolicy) -> Self {
+        self.conflict_policy = policy;
+        self
+    }
+
+    /// Declares an extension point that plugins can subscribe to with
+    /// [`Plugin::hooks`], to be called with [`invoke_hook`](Self::invoke_hook).
+    pub fn with_hook(mut self, point: &str) -> Self {
+        if !self.hook_points.iter().any(|p| p == point) {
+            self.hook_points.push(point.to_string());
+        }
+        self
+    }
+
+    /// Loads and registers the plugins of every entry, failing at the first
+    /// entry that cannot be activated.
+    ///
+    /// Plugins are registered after the plugins they depend on, and otherwise
+    /// in order of their entry's priority.
+    pub fn activate_plugins(mut self) -> Result<PluginManager, PluginError> {
+        self.activate(true)?;
+        Ok(self)
+    }
+
+    /// Activates every plugin that can be loaded and registered, instead of
+    /// stopping at the first failure like [`activate_plugins`](Self::activate_plugins).
+    ///
+    /// Each entry that could not be loaded or registered is listed in the
+    /// returned [`ActivationReport`]. An error is only returned when the
+    /// plugin metadata itself is unavailable.
+    pub fn activate_available_plugins(
+        mut self,
+    ) -> Result<(PluginManager, ActivationReport), PluginError> {
+        let report = self.activate(false)?;
+        Ok((self, report))
+    }
+
+    /// Loads the libraries of every entry, then registers their plugins in
+    /// dependency order. In `strict` mode the first failure is returned,
+    /// otherwise failures are collected in the report.
+    fn activate(&mut self, strict: bool) -> Result<ActivationReport, PluginError> {
+        let mut report = ActivationReport::default();
+        let registrations = self.collect_registrations(strict, &mut report)?;
+        let libraries: Vec<EntryLibrary> = registrations
+            .iter()
+            .flat_map(|(group_or_name, plugin_entry)| {
+                Self::entry_libraries(group_or_name, plugin_entry)
+            })
+            .collect();
+        let mut pending = Vec::new();
+        for library in &libraries {
+            let (handle, plugins) = match self.load_plugin(library.path) {
+                Ok((handle, plugins)) => (Arc::new(handle), plugins),
+                Err(error) => {
+                    report.fail(strict, library, error)?;
+                    continue;
+                }
+            };
+            let library_path = Self::library_path(library.path);
+            let library_hash = descriptor::library_hash(&library_path)
+                .inspect_err(|error| {
+                    log::warn!("Failed to hash library {}: {error}", library_path.display())
+                })
+                .ok();
+            for mut plugin in plugins {
+                let descriptor = match Self::plugin_descriptor(plugin.as_ref(), library.version) {
+                    Ok(descriptor) => PluginDescriptor {
+                        path: Some(library_path.clone()),
+                        library_hash: library_hash.clone(),
+                        ..descriptor
+                    },
+                    Err(error) => {
+                        report.fail(strict, library, error)?;
+                        continue;
+                    }
+                };
+                if let Err(error) = Self::configure_plugin(plugin.as_mut(), library.config) {
+                    report.fail(strict, library, error)?;
+                    continue;
+                }
+                let mut depends_on = library.depends_on.to_vec();
+                depends_on.extend(plugin.depends_on());
+                depends_on.sort();
+                depends_on.dedup();
+                let plugin_info = PluginInfo {
+                    plugin: plugin.into(),
+                    group: library.group.clone(),
+                    descriptor,
+                    depends_on,
+                    health: AtomicU8::default(),
+                    library: Some(handle.clone()),
+                };
+                pending.push((library, plugin_info));
+            }
+        }
+
+        while !pending.is_empty() {
+            let ready = pending.iter().position(|(_, plugin_info)| {
+                plugin_info
+                    .depends_on
+                    .iter()
+                    .all(|dependency| self.has_plugin_named(dependency))
+            });
+            if let Some(index) = ready {
+                let (library, plugin_info) = pending.remove(index);
+                match self.insert_plugin(plugin_info) {
+                    Ok(Some(key)) => report.activated.push(key),
+                    Ok(None) => {}
+                    Err(error) => report.fail(strict, library, error)?,
+                }
+                continue;
+            }
+            // Nothing can be registered: reject a plugin whose dependency is
+            // neither registered nor pending, or else the plugins of a cycle.
+            // The plugins depending on a rejected one are then rejected in turn.
+            let graph: IndexMap<String, Vec<String>> = pending
+                .iter()
+                .map(|(_, plugin_info)| {
+                    let unmet = plugin_info
+                        .depends_on
+                        .iter()
+                        .filter(|dependency| !self.has_plugin_named(dependency))
+                        .cloned()
+                        .collect();
+                    (plugin_info.descriptor.name.clone(), unmet)
+                })
+                .collect();
+            let missing = pending.iter().find_map(|(_, plugin_info)| {
+                let name = &plugin_info.descriptor.name;
+                let dependency = graph[name]
+                    .iter()
+                    .find(|dependency| !graph.contains_key(*dependency))?;
+                Some((name.clone(), dependency.clone()))
+            });
+            let cycle = match missing {
+                Some(_) => Vec::new(),
+                None => dependency_cycle(&graph),
+            };
+            let rejected = match &missing {
+                Some((name, _)) => std::slice::from_ref(name),
+                None => cycle.as_slice(),
+            };
+            let (rejected, kept) = std::mem::take(&mut pending)
+                .into_iter()
+                .partition(|(_, plugin_info)| rejected.contains(&plugin_info.descriptor.name));
+            pending = kept;
+            for (library, plugin_info) in rejected {
+                let name = plugin_info.descriptor.name.clone();
+                let error = match &missing {
+                    Some((_, dependency)) => PluginError::MissingDependency {
+                        name,
+                        dependency: dependency.clone(),
+                    },
+                    None => PluginError::DependencyCycle {
+                        name,
+                        cycle: cycle.clone(),
+                    },
+                };
+                log::error!("{error}");
+                report.fail(strict, library, error)?;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Gathers the entries from the plugin metadata, those added with
+    /// [`with_path`](Self::with_path) and the libraries found in plugin directories.
+    ///
+    /// The metadata is optional when plugins were added programmatically and
+    /// the configuration source is unavailable, such as an unset
+    /// `CARGO_MANIFEST_PATH`. A plugin directory that cannot be scanned is
+    /// recorded in `report` unless `strict` is set.
+    fn collect_registrations(
+        &self,
+        strict: bool,
+        report: &mut ActivationReport,
+    ) -> Result<Vec<(GroupOrName, PluginEntry)>, PluginError> {
+        let mut registrations = Vec::new();
+        let mut plugin_dirs = Vec::new();
+        let programmatic = !self.plugin_path.is_empty() || !self.plugin_dirs.is_empty();
+        if !programmatic || self.config_source.is_available() {
+            let meta_data = self.get_plugin_metadata()?;
+            log::debug!("Plugin metadata: {:?}", meta_data);
+            if meta_data.plugins.is_none() && meta_data.plugin_dirs.is_none() && !programmatic {
+                log::error!("No plugin metadata found in manifest");
+                return Err(PluginError::Metadata {
+                    path: self.config_source.display_path(),
+                    source: "No plugin metadata found in manifest".into(),
+                });
+            }
+            if let Some(plugin_config) = meta_data.plugins {
+                for (group_or_name, plugin_entry) in plugin_config {
+                    registrations.push((group_or_name, plugin_entry));
+                }
+            }
+            plugin_dirs.extend(meta_data.plugin_dirs.unwrap_or_default());
+        }
+        if !self.plugin_path.is_empty() {
+            for entry in &self.plugin_path {
+                for (group_or_name, plugin_entry) in entry {
+                    registrations.push((group_or_name.clone(), plugin_entry.clone()));
+                }
+            }
+        }
+        registrations.retain(|(group_or_name, plugin_entry)| {
+            if !plugin_entry.is_enabled() {
+                log::info!("Skipping disabled plugin entry: {group_or_name}");
+            }
+            plugin_entry.is_enabled()
+        });
+        plugin_dirs.extend(self.plugin_dirs.iter().cloned());
+        for plugin_dir in plugin_dirs {
+            let paths = match plugin_dir.scan() {
+                Ok(paths) => paths,
+                Err(error) => {
+                    log::error!("{error}");
+                    report.fail_dir(strict, &plugin_dir, error)?;
+                    continue;
+                }
+            };
+            for path in paths {
+                let Some(path_string) = path.to_str().map(str::to_string) else {
+                    let error = PluginError::InvalidPath { path };
+                    log::error!("{error}");
+                    report.fail_dir(strict, &plugin_dir, error)?;
+                    continue;
+                };
+                registrations.push(Self::path_entry(
+                    &path,
+                    path_string,
+                    plugin_dir.group.as_deref(),
+                ));
+            }
+        }
+        registrations.sort_by_key(|(_, plugin_entry)| std::cmp::Reverse(plugin_entry.priority()));
+        Ok(registrations)
+    }
+
+    /// Builds the entry for a single shared object, named after its file stem.
+    fn path_entry(
+        path: &Path,
+        path_string: PathString,
+        group: Option<&str>,
+    ) -> (GroupOrName, PluginEntry) {
+        let entry_name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| path_string.clone());
+        match group {
+            Some(group) => (
+                group.to_string(),
+                PluginEntry::Group(IndexMap::from([(entry_name, path_string)])),
+            ),
+            None => (entry_name, PluginEntry::Individual(path_string)),
+        }
+    }
+
+    /// Lists the shared objects of an entry.
+    fn entry_libraries<'a>(
+        group_or_name: &'a str,
+        plugin_entry: &'a PluginEntry,
+    ) -> Vec<EntryLibrary<'a>> {
+        match plugin_entry {
+            PluginEntry::Individual(path) => {
+                log::debug!("Loading individual plugin: {group_or_name} {path}");
+                vec![EntryLibrary {
+                    entry: group_or_name,
+                    group: None,
+                    path: path.as_str(),
+                    config: None,
+                    depends_on: &[],
+                    version: None,
+                }]
+            }
+            PluginEntry::Detailed(spec) => {
+                log::debug!("Loading plugin: {group_or_name} {}", spec.path);
+                vec![EntryLibrary {
+                    entry: group_or_name,
+                    group: spec.group.clone(),
+                    path: spec.path.as_str(),
+                    config: spec.config.as_ref(),
+                    depends_on: &spec.depends_on,
+                    version: spec.version.as_ref(),
+                }]
+            }
+            PluginEntry::Group(group_plugins) => group_plugins
+                .iter()
+                .map(|(name, path)| {
+                    log::debug!("Loading plugin group: {group_or_name}, {name} {path}");
+                    EntryLibrary {
+                        entry: name.as_str(),
+                        group: Some(group_or_name.to_string()),
+                        path: path.as_str(),
+                        config: None,
+                        depends_on: &[],
+                        version: None,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Builds the descriptor of the plugin and checks its version against the
+    /// requirement of its entry, if there is one.
+    fn plugin_descriptor(
+        plugin: &dyn Plugin,
+        requirement: Option<&VersionReq>,
+    ) -> Result<PluginDescriptor, PluginError> {
+        let descriptor = PluginDescriptor::from_plugin(plugin).inspect_err(|error| {
+            log::error!("{error}");
+        })?;
+        if let Some(requirement) = requirement
+            && !descriptor
+                .version
+                .as_ref()
+                .is_some_and(|version| requirement.matches(version))
         {
-            let meta: Result<Metadata, toml::de::Error> =
-                toml::from_str(&toml::to_string(meta_data).unwrap());
-            meta.unwrap()
+            let error = PluginError::VersionMismatch {
+                name: descriptor.name,
+                required: requirement.clone(),
+                found: descriptor.version,
+            };
+            log::error!("{error}");
+            return Err(error);
+        }
+        Ok(descriptor)
+    }
+
+    /// Passes the `config` table of its entry to the plugin, if there is one.
+    fn configure_plugin(
+        plugin: &mut dyn Plugin,
+        config: Option<&toml::Table>,
+    ) -> Result<(), PluginError> {
+        let Some(config) = config else {
+            return Ok(());
+        };
+        let name = plugin.name();
+        panic::catch_panic(&name, || {
+            plugin.configure(&toml::Value::Table(config.clone()))
+        })?
+        .map_err(|error| {
+            let error = PluginError::configuration(plugin, &name, error);
+            log::error!("{error}");
+            error
+        })
+    }
+
+    /// Registers each plugin by the name returned by the plugin's `name` method.
+    /// It allows for plugins to be grouped together for easier management within
+    /// a single crated if there share similar traits.
+    ///
+    /// When `group` is `None` the plugin's own [`Plugin::group`] is used.
+    /// Name clashes are resolved according to the manager's [`ConflictPolicy`].
+    pub fn register_plugin(
+        &mut self,
+        plugin: Box<dyn Plugin>,
+        group: Option<String>,
+    ) -> Result<(), PluginError> {
+        let descriptor = Self::plugin_descriptor(plugin.as_ref(), None)?;
+        let depends_on = plugin.depends_on();
+        self.insert_plugin(PluginInfo {
+            plugin: plugin.into(),
+            group,
+            descriptor,
+            depends_on,
+            health: AtomicU8::default(),
+            library: None,
+        })
+        .map(|_| ())
+    }
+
+    /// Registers a plugin and returns the name it was registered under, or
+    /// `None` if the [`ConflictPolicy`] discarded it.
+    fn insert_plugin(
+        &mut self,
+        mut plugin_info: PluginInfo,
+    ) -> Result<Option<String>, PluginError> {
+        if plugin_info.group.is_none() {
+            plugin_info.group = plugin_info.plugin.group();
+        }
+        log::info!("Registering plugin: {:?}", plugin_info.plugin.name());
+        let name = plugin_info.plugin.name().to_string();
+        for subscription in &plugin_info.descriptor.hooks {
+            if !self.hook_points.contains(&subscription.point) {
+                log::warn!(
+                    "Plugin '{name}' subscribes to the undeclared extension point '{}'",
+                    subscription.point
+                );
+            }
+        }
+
+        if let Some(dependency) = plugin_info
+            .depends_on
+            .iter()
+            .find(|dependency| !self.has_plugin_named(dependency))
+        {
+            let error = PluginError::MissingDependency {
+                name,
+                dependency: dependency.clone(),
+            };
+            log::error!("{error}");
+            return Err(error);
+        }
+        let Some(key) = self.registration_name(name.clone(), plugin_info.group.as_deref())? else {
+            return Ok(None);
+        };
+        // The plugin is not shared with anything before it is registered.
+        let on_load = match Arc::get_mut(&mut plugin_info.plugin) {
+            Some(plugin) => panic::catch_panic(&name, || plugin.on_load())?,
+            None => Ok(()),
+        };
+        if let Err(error) = on_load {
+            let error =
+                PluginError::lifecycle(plugin_info.plugin.as_ref(), &name, "on_load", error);
+            log::error!("{error}");
+            return Err(error);
+        }
+        // A replaced plugin keeps its position in the registry.
+        let (index, previous) = self.plugins.insert_full(key.clone(), plugin_info);
+        if let Some(previous) = previous {
+            self.unload_plugin(previous);
+        }
+        let plugin = self.plugins[index].plugin.as_ref();
+        // The plugin stays registered; the panic is kept with the lifecycle errors.
+        let on_activate =
+            panic::catch_panic(&name, || plugin.try_on_activate()).and_then(|result| {
+                result.map_err(|error| PluginError::lifecycle(plugin, &name, "on_activate", error))
+            });
+        if let Err(error) = on_activate {
+            log::error!("{error}");
+            self.lifecycle_errors.push(error);
+        }
+        Ok(Some(key))
+    }
+
+    /// The name a plugin called `name` is registered under, following the
+    /// [`ConflictPolicy`]. `None` means the plugin is discarded.
+    fn registration_name(
+        &self,
+        name: String,
+        group: Option<&str>,
+    ) -> Result<Option<String>, PluginError> {
+        if !self.plugins.contains_key(&name) {
+            return Ok(Some(name));
+        }
+        match self.conflict_policy {
+            ConflictPolicy::Error => {}
+            ConflictPolicy::KeepFirst => {
+                log::warn!("Plugin '{name}' already registered, keeping the existing plugin");
+                return Ok(None);
+            }
+            ConflictPolicy::ReplaceExisting => {
+                log::warn!("Plugin '{name}' already registered, replacing it");
+                return Ok(Some(name));
+            }
+            ConflictPolicy::RenameWithGroupPrefix => {
+                if let Some(group) = group {
+                    let renamed = format!("{group}::{name}");
+                    if !self.plugins.contains_key(&renamed) {
+                        log::warn!(
+                            "Plugin '{name}' already registered, registering as '{renamed}'"
+                        );
+                        return Ok(Some(renamed));
+                    }
+                    let error = PluginError::DuplicatePlugin {
+                        name: renamed,
+                        group: Some(group.to_string()),
+                    };
+                    log::error!("{error}");
+                    return Err(error);
+                }
+            }
+        }
+        let error = PluginError::DuplicatePlugin {
+            name,
+            group: group.map(str::to_string),
+        };
+        log::error!("{error}");
+        Err(error)
+    }
+
+    /// Runs the `on_deactivate` and `on_unload` hooks of a plugin that has
+    /// been removed from the registry, before it is dropped.
+    fn unload_plugin(&mut self, mut plugin_info: PluginInfo) {
+        let name = plugin_info.plugin.name();
+        let plugin = &mut plugin_info.plugin;
+        let on_deactivate =
+            panic::catch_panic(&name, || plugin.try_on_deactivate()).and_then(|result| {
+                result.map_err(|error| {
+                    PluginError::lifecycle(plugin.as_ref(), &name, "on_deactivate", error)
+                })
+            });
+        let on_unload = panic::catch_panic(&name, || match Arc::get_mut(plugin) {
+            Some(plugin) => plugin.on_unload(),
+            None => Err("the plugin is still executing on a timed out worker thread".into()),
+        })
+        .and_then(|result| {
+            result
+                .map_err(|error| PluginError::lifecycle(plugin.as_ref(), &name, "on_unload", error))
+        });
+        for error in [on_deactivate, on_unload]
+            .into_iter()
+            .filter_map(Result::err)
+        {
+            log::error!("{error}");
+            self.lifecycle_errors.push(error);
+        }
+    }
+
+    /// Deregisters the plugin with the given name.
+    ///
+    /// Plugins that other registered plugins depend on are refused with
+    /// [`PluginError::HasDependents`]; see [`force_deregister_plugin`](Self::force_deregister_plugin).
+    /// The plugin is removed even if its `on_unload` hook fails; the error
+    /// is kept for [`take_lifecycle_errors`](Self::take_lifecycle_errors).
+    pub fn deregister_plugin(&mut self, name: &str) -> Result<String, PluginError> {
+        let dependents = self.dependents(name);
+        if !dependents.is_empty() {
+            let error = PluginError::HasDependents {
+                name: name.to_string(),
+                dependents,
+            };
+            log::error!("{error}");
+            return Err(error);
+        }
+        self.force_deregister_plugin(name)
+    }
+
+    /// Deregisters the plugin with the given name, even if other registered
+    /// plugins depend on it.
+    pub fn force_deregister_plugin(&mut self, name: &str) -> Result<String, PluginError> {
+        log::info!("De-registering plugin: {}", name);
+        let plugin_info = self.plugins.shift_remove(name).ok_or_else(|| {
+            let error = PluginError::NotFound {
+                name: name.to_string(),
+            };
+            log::error!("{error}");
+            error
+        })?;
+        let name = plugin_info.plugin.name();
+        self.unload_plugin(plugin_info);
+        Ok(name)
+    }
+
+    /// Deregisters every plugin in the reverse of the order they were
+    /// registered, each one before the plugins it depends on.
+    pub fn deregister_all_plugins(&mut self) -> Vec<String> {
+        let mut names = Vec::with_capacity(self.plugins.len());
+        while let Some(name) = self
+            .plugins
+            .keys()
+            .rev()
+            .find(|name| self.dependents(name).is_empty())
+            .or_else(|| self.plugins.keys().next_back())
+            .cloned()
+        {
+            if let Some(plugin_info) = self.plugins.shift_remove(&name) {
+                self.unload_plugin(plugin_info);
+            }
+            log::info!("De-registered plugin: {}", name);
+            names.push(name);
+        }
+        names
+    }
+
+    /// The names of the registered plugins that depend on `name`.
+    ///
+    /// Dependencies name plugins rather than registry keys, so the dependents
+    /// of a plugin registered under another key, such as with
+    /// [`ConflictPolicy::RenameWithGroupPrefix`], are those naming the plugin.
+    pub fn dependents(&self, name: &str) -> Vec<String> {
+        let plugin_name = self
+            .plugins
+            .get(name)
+            .map_or(name, |plugin_info| plugin_info.descriptor.name.as_str());
+        let mut dependents: Vec<String> = self
+            .plugins
+            .iter()
+            .filter(|(_, plugin_info)| plugin_info.depends_on.iter().any(|d| d == plugin_name))
+            .map(|(dependent, _)| dependent.clone())
+            .collect();
+        dependents.sort();
+        dependents
+    }
+
+    /// Whether a plugin called `name` is registered, under its own name or
+    /// another key.
+    fn has_plugin_named(&self, name: &str) -> bool {
+        self.plugins.contains_key(name)
+            || self
+                .plugins
+                .values()
+                .any(|plugin_info| plugin_info.descriptor.name == name)
+    }
+
+    /// Returns the errors from `on_unload` hooks, and the panics in
+    /// `on_activate` and `on_deactivate` hooks, since the last call, leaving
+    /// none behind.
+    pub fn take_lifecycle_errors(&mut self) -> Vec<PluginError> {
+        std::mem::take(&mut self.lifecycle_errors)
+    }
+
+    /// Deregisters every plugin loaded from the shared object at `path`,
+    /// closing the library once the last of its plugins has been dropped.
+    ///
+    /// Returns the names of the deregistered plugins, or
+    /// [`PluginError::LibraryNotLoaded`] if no registered plugin came from `path`.
+    pub fn unload_library(&mut self, path: &str) -> Result<Vec<String>, PluginError> {
+        let library_path = Self::library_path(path);
+        let names: Vec<String> = self
+            .plugins
+            .iter()
+            .filter(|(_, plugin_info)| plugin_info.descriptor.path.as_ref() == Some(&library_path))
+            .map(|(name, _)| name.clone())
+            .collect();
+        if names.is_empty() {
+            let error = PluginError::LibraryNotLoaded { path: library_path };
+            log::error!("{error}");
+            return Err(error);
+        }
+        for name in &names {
+            let dependents: Vec<String> = self
+                .dependents(name)
+                .into_iter()
+                .filter(|dependent| !names.contains(dependent))
+                .collect();
+            if !dependents.is_empty() {
+                let error = PluginError::HasDependents {
+                    name: name.clone(),
+                    dependents,
+                };
+                log::error!("{error}");
+                return Err(error);
+            }
+        }
+        for name in &names {
+            self.force_deregister_plugin(name)?;
+        }
+        log::info!("Unloaded library: {}", library_path.display());
+        Ok(names)
+    }
+
+    /// The canonical form of a library path, used to match plugins to the
+    /// shared object they were loaded from.
+    fn library_path(path: &str) -> PathBuf {
+        std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+    }
+
+    /// Loads a plugin from a shared object file and registers it to the plugin manager.
+    ///
+    /// The returned [`Library`] must outlive the plugins created from it.
+    ///
+    /// The library's [`AbiDescriptor`] is checked before `create_plugins` is
+    /// called, and a library built against a different `plugin_manager` or
+    /// `rustc` is rejected with [`PluginError::AbiMismatch`].
+    pub fn load_plugin(&self, filename: &str) -> PluginResult {
+        let path = Path::new(filename);
+
+        if !path.exists() {
+            let error = PluginError::FileNotFound {
+                path: path.to_path_buf(),
+            };
+            log::error!("{error}");
+            return Err(error);
+        } else {
+            log::debug!("Attempting to load plugin: {}", filename);
+        }
+
+        let library = unsafe { Library::new(path) }.map_err(|source| PluginError::LibraryLoad {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        log::debug!("Library loaded successfully");
+
+        let descriptor: Symbol<*const AbiDescriptor> = unsafe {
+            library.get(ABI_DESCRIPTOR_SYMBOL.as_bytes())
+        }
+        .map_err(|source| PluginError::MissingSymbol {
+            path: path.to_path_buf(),
+            symbol: ABI_DESCRIPTOR_SYMBOL.to_string(),
+            source,
+        })?;
+        let descriptor = unsafe { &**descriptor };
+        if let Err(error) = descriptor.check(path) {
+            log::error!("{error}");
+            return Err(error);
+        }
+        if let (Some(name), Some(version)) =
+            (descriptor.package_name(), descriptor.package_version())
+        {
+            log::debug!("ABI descriptor matches for {name} {version}");
         } else {
-            Metadata { plugins: None }
+            log::debug!("ABI descriptor matches");
+        }
+
+        let create_plugin: Symbol<PluginCreate> = unsafe {
+            library.get(CREATE_PLUGINS_SYMBOL.as_bytes())
         }
-        // metadata
+        .map_err(|source| PluginError::MissingSymbol {
+            path: path.to_path_buf(),
+            symbol: CREATE_PLUGINS_SYMBOL.to_string(),
+            source,
+        })?;
+        log::debug!("Found create_plugins symbol");
+
+        let plugins = unsafe { create_plugin() }.map_err(|message| {
+            let error = PluginError::PluginPanicked {
+                name: path.display().to_string(),
+                message,
+            };
+            log::error!("{error}");
+            error
+        })?;
+        log::debug!("Plugin created successfully");
+
+        Ok((library, plugins))
+    }
+
+    /// Reads the plugin metadata from the manager's [`PluginConfigSource`].
+    /// By default this is the manifest named by the environment variable
+    /// CARGO_MANIFEST_PATH. The metadata is in TOML format and contains
+    /// the following structure:
+    ///
+    /// ```toml
+    /// [package.metadata.plugins]
+    /// plugin_a = "/path/to/plugin_a.so"
+    ///
+    /// [package.metadata.plugins.inventory]
+    /// inventory_plugin = "/path/to/inventory_plugin.so"
+    /// ```
+    ///
+    /// Returns [`PluginError::Metadata`] if the metadata cannot be read or parsed.
+    pub fn get_plugin_metadata(&self) -> Result<Metadata, PluginError> {
+        self.config_source.load()
+    }
+
+    /// Adds the shared object at `path` to the plugins loaded by
+    /// [`activate_plugins`](Self::activate_plugins), alongside those in the
+    /// manifest. Its plugins are registered under `group` when one is given.
+    ///
+    /// The entry is named after the file stem of `path`.
+    pub fn with_path(mut self, path: &str, group: Option<&str>) -> Result<Self, PluginError> {
+        let path = Path::new(&path);
+        if path.exists() {
+            let path_string = if let Some(path_str) = path.to_str() {
+                path_str.to_string()
+            } else {
+                return Err(PluginError::InvalidPath {
+                    path: path.to_path_buf(),
+                });
+            };
+            let (group_or_name, plugin_entry) = Self::path_entry(path, path_string, group);
+            self.plugin_path
+                .push(IndexMap::from([(group_or_name, plugin_entry)]));
+            Ok(self)
+        } else {
+            Err(PluginError::FileNotFound {
+                path: path.to_path_buf(),
+            })
+        }
+    }
+
+    /// Scans `dir` for plugin libraries when activating plugins, registering
+    /// every plugin found under `group` when one is given.
+    ///
+    /// Use [`with_plugin_dir_options`](Self::with_plugin_dir_options) for a
+    /// recursive or filtered scan.
+    pub fn with_plugin_dir(self, dir: &str, group: Option<&str>) -> Result<Self, PluginError> {
+        let mut plugin_dir = PluginDir::new(dir);
+        plugin_dir.group = group.map(str::to_string);
+        self.with_plugin_dir_options(plugin_dir)
+    }
+
+    /// Scans the directory described by `plugin_dir` when activating plugins.
+    pub fn with_plugin_dir_options(mut self, plugin_dir: PluginDir) -> Result<Self, PluginError> {
+        let path = Path::new(&plugin_dir.path);
+        if !path.is_dir() {
+            return Err(PluginError::FileNotFound {
+                path: path.to_path_buf(),
+            });
+        }
+        self.plugin_dirs.push(plugin_dir);
+        Ok(self)
+    }
+
+    /// Gets the plugin with the given name.
+    pub fn get_plugin(&self, name: &str) -> Option<&PluginInfo> {
+        self.plugins.get(name)
+    }
+
+    /// Gets the plugins of a group, in registration order.
+    pub fn get_plugins_by_group(&self, group: &str) -> Vec<&PluginInfo> {
+        self.plugins
+            .values()
+            .filter(|plugin_info| plugin_info.group.as_deref() == Some(group))
+            .collect()
+    }
+
+    /// Gets the plugins with the given tag, in registration order.
+    pub fn find_by_tag(&self, tag: &str) -> Vec<&PluginInfo> {
+        self.plugins
+            .values()
+            .filter(|plugin_info| plugin_info.descriptor.has_tag(tag))
+            .collect()
+    }
+
+    /// Gets the plugins that declare the given capability, in registration order.
+    pub fn find_by_capability(&self, capability: &str) -> Vec<&PluginInfo> {
+        self.plugins
+            .values()
+            .filter(|plugin_info| plugin_info.descriptor.has_capability(capability))
+            .collect()
+    }
+
+    /// Gets all the **names** of the registered plugins, in registration order.
+    pub fn get_all_plugin_names(&self) -> Vec<&String> {
+        self.plugins.keys().collect()
+    }
+
+    /// Gets all the **names** and **groups** of the registered plugins, in
+    /// registration order.
+    pub fn get_all_plugin_names_and_groups(&self) -> Vec<(String, Option<String>)> {
+        self.plugins
+            .iter()
+            .map(|(name, plugin_info)| (name.clone(), plugin_info.group.clone()))
+            .collect()
+    }
+
+    /// Executes a plugin with the provided context.
+    ///
+    /// A panic in the plugin is returned as [`PluginError::PluginPanicked`]
+    /// and, with [`with_panic_quarantine`](Self::with_panic_quarantine), keeps
+    /// the plugin from being executed again.
+    pub fn execute_plugin(&self, name: &str, context: &dyn Any) -> Result<(), PluginError> {
+        self.invoke(name, |plugin| plugin.execute(context))
+    }
+
+    /// Executes every plugin of a group with the provided context, run as set
+    /// by `mode` and stopped early as set by `policy`.
+    ///
+    /// Each plugin is executed as with [`execute_plugin`](Self::execute_plugin)
+    /// and its result is recorded in the report under its name. A group
+    /// without plugins gives an empty report.
+    pub fn execute_group(
+        &self,
+        group: &str,
+        context: &(dyn Any + Sync),
+        mode: ExecutionMode,
+        policy: FailurePolicy,
+    ) -> GroupReport {
+        let names = self
+            .plugins
+            .iter()
+            .filter(|(_, plugin_info)| plugin_info.group.as_deref() == Some(group))
+            .map(|(name, _)| name.clone())
+            .collect();
+        group::execute(names, mode, policy, |name| {
+            self.execute_plugin(name, context)
+        })
+    }
+
+    /// Executes a plugin with a context of type `C` and returns its output
+    /// of type `O`, as produced by [`Plugin::execute_any`].
+    ///
+    /// Fails with [`PluginError::ContextMismatch`] if the plugin expects
+    /// another type of context, and [`PluginError::OutputMismatch`] if its
+    /// output is not an `O`. Panics are handled as in [`execute_plugin`](Self::execute_plugin).
+    pub fn execute_typed<C: Any, O: Any>(&self, name: &str, context: &C) -> Result<O, PluginError> {
+        let output = self.invoke(name, |plugin| plugin.execute_any(context))?;
+        output.downcast::<O>().map(|output| *output).map_err(|_| {
+            let error = PluginError::OutputMismatch {
+                name: name.to_string(),
+                expected: std::any::type_name::<O>(),
+            };
+            log::error!("{error}");
+            error
+        })
+    }
+
+    /// Calls every plugin subscribed to an extension point with the provided
+    /// context, through [`Plugin::handle_hook`], and collects their outputs.
+    ///
+    /// Subscribers are called by descending priority, then in registration
+    /// order. A failing subscriber does not stop the others; its error is
+    /// recorded in the report and panics are handled as in
+    /// [`execute_plugin`](Self::execute_plugin). Fails with
+    /// [`PluginError::UnknownHook`] if the point was not declared with
+    /// [`with_hook`](Self::with_hook).
+    pub fn invoke_hook(&self, point: &str, context: &dyn Any) -> Result<HookReport, PluginError> {
+        if !self.hook_points.iter().any(|p| p == point) {
+            let error = PluginError::UnknownHook {
+                point: point.to_string(),
+            };
+            log::error!("{error}");
+            return Err(error);
+        }
+        let mut report = HookReport::default();
+        for name in self.get_hook_subscribers(point) {
+            let result = self.invoke(name, |plugin| plugin.handle_hook(point, context));
+            report.results.insert(name.clone(), result);
+        }
+        Ok(report)
+    }
+
+    /// Gets the names of the plugins subscribed to an extension point, in the
+    /// order [`invoke_hook`](Self::invoke_hook) calls them.
+    pub fn get_hook_subscribers(&self, point: &str) -> Vec<&String> {
+        let mut subscribers: Vec<_> = self
+            .plugins
+            .iter()
+            .filter_map(|(name, plugin_info)| {
+                let subscription = plugin_info
+                    .descriptor
+                    .hooks
+                    .iter()
+                    .find(|subscription| subscription.point == point)?;
+                Some((name, subscription.priority))
+            })
+            .collect();
+        // The sort is stable, so equal priorities keep their registration order.
+        subscribers.sort_by_key(|(_, priority)| std::cmp::Reverse(*priority));
+        subscribers.into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// Starts a [`Pipeline`] whose first stage executes the plugin `first`.
+    /// Further stages are added with [`Pipeline::then`].
+    pub fn pipeline(&self, first: &str) -> Pipeline<'_> {
+        Pipeline::new(self, first)
+    }
+
+    /// Executes a plugin with a [`CancellationToken`], through
+    /// [`Plugin::execute_cancellable`]. The token can be cancelled from
+    /// another thread to ask the plugin to stop.
+    pub fn execute_plugin_cancellable(
+        &self,
+        name: &str,
+        context: &dyn Any,
+        token: &CancellationToken,
+    ) -> Result<(), PluginError> {
+        self.invoke(name, |plugin| plugin.execute_cancellable(context, token))
+    }
+
+    /// Executes a plugin on a worker thread, waiting at most `timeout` for it
+    /// to finish.
+    ///
+    /// When the timeout expires the plugin's [`CancellationToken`] is
+    /// cancelled, the plugin is marked [`PluginHealth::Unhealthy`] and
+    /// [`PluginError::Timeout`] is returned. The worker thread is left to
+    /// finish on its own, keeping the plugin and its library loaded until then.
+    pub fn execute_plugin_with_timeout<C: Any + Send>(
+        &self,
+        name: &str,
+        context: C,
+        timeout: Duration,
+    ) -> Result<(), PluginError> {
+        let plugin_info = self.executable_plugin(name)?;
+        let token = CancellationToken::new();
+        let (sender, receiver) = mpsc::channel();
+        let worker = {
+            let name = name.to_string();
+            let plugin = plugin_info.plugin.clone();
+            let library = plugin_info.library.clone();
+            let token = token.clone();
+            move || {
+                let result =
+                    panic::catch_panic(&name, || plugin.execute_cancellable(&context, &token))
+                        .and_then(|result| {
+                            result.map_err(|error| {
+                                PluginError::execution(plugin.as_ref(), &name, error)
+                            })
+                        });
+                // The receiver is gone if the timeout already expired.
+                let _ = sender.send(result);
+                // The plugin must not outlive the library its code lives in.
+                drop(plugin);
+                drop(library);
+            }
+        };
+        std::thread::Builder::new()
+            .name(format!("plugin-{name}"))
+            .spawn(worker)
+            .map_err(|error| PluginError::Execution {
+                name: name.to_string(),
+                source: error.into(),
+            })?;
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => self.record_panic(plugin_info, result),
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                token.cancel();
+                plugin_info.set_health(PluginHealth::Unhealthy);
+                let error = PluginError::Timeout {
+                    name: name.to_string(),
+                    timeout,
+                };
+                log::error!("{error}");
+                Err(error)
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(PluginError::PluginPanicked {
+                name: name.to_string(),
+                message: "the worker thread exited without a result".to_string(),
+            }),
+        }
+    }
+
+    /// Calls a registered plugin that is not quarantined, catching panics.
+    fn invoke<T>(
+        &self,
+        name: &str,
+        call: impl FnOnce(&dyn Plugin) -> Result<T, Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Result<T, PluginError> {
+        let plugin_info = self.executable_plugin(name)?;
+        let result =
+            panic::catch_panic(name, || call(plugin_info.plugin.as_ref())).and_then(|result| {
+                result.map_err(|error| {
+                    PluginError::execution(plugin_info.plugin.as_ref(), name, error)
+                })
+            });
+        self.record_panic(plugin_info, result)
+    }
+
+    /// Gets a registered plugin that is not quarantined.
+    fn executable_plugin(&self, name: &str) -> Result<&PluginInfo, PluginError> {
+        let plugin_info = self.get_plugin(name).ok_or_else(|| {
+            let error = PluginError::NotFound {
+                name: name.to_string(),
+            };
+            log::error!("{error}");
+            error
+        })?;
+        if plugin_info.is_quarantined() {
+            let error = PluginError::Quarantined {
+                name: name.to_string(),
+            };
+            log::error!("{error}");
+            return Err(error);
+        }
+        Ok(plugin_info)
+    }
+
+    /// Quarantines the plugin if the call panicked and the manager is set to.
+    fn record_panic<T>(
+        &self,
+        plugin_info: &PluginInfo,
+        result: Result<T, PluginError>,
+    ) -> Result<T, PluginError> {
+        if matches!(result, Err(PluginError::PluginPanicked { .. })) && self.quarantine_on_panic {
+            log::warn!("Quarantining plugin '{}'", plugin_info.plugin.name());
+            plugin_info.set_health(PluginHealth::Quarantined);
+        }
+        result
+    }
+
+    /// Marks a plugin as [`PluginHealth::Healthy`], lifting a quarantine.
+    pub fn reset_health(&self, name: &str) -> Result<(), PluginError> {
+        let plugin_info = self.get_plugin(name).ok_or_else(|| PluginError::NotFound {
+            name: name.to_string(),
+        })?;
+        plugin_info.set_health(PluginHealth::Healthy);
+        Ok(())
+    }
+
+    /// Gets the names of the quarantined plugins, in registration order.
+    pub fn get_quarantined_plugin_names(&self) -> Vec<&String> {
+        self.plugins
+            .iter()
+            .filter(|(_, plugin_info)| plugin_info.is_quarantined())
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Utility method to downcast a plugin to a specific type
+    ///
+    /// It allows you to safely access the plugin's fields and methods,
+    /// not found in the `Plugin` trait.
+    pub fn with_any<P: 'static>(&self, name: &str) -> Result<&P, PluginError> {
+        if let Some(plugin_info) = self.get_plugin(name) {
+            match plugin_info.plugin.as_any().downcast_ref::<P>() {
+                Some(plugin) => Ok(plugin),
+                None => Err(PluginError::Downcast {
+                    name: name.to_string(),
+                    type_name: std::any::type_name::<P>(),
+                }),
+            }
+        } else {
+            Err(PluginError::NotFound {
+                name: name.to_string(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn manifest_file() -> String {
+        "../tests/plugin_mods/Cargo.toml".to_string()
+    }
+
+    fn set_env_var() {
+        unsafe {
+            std::env::set_var("CARGO_MANIFEST_PATH", manifest_file());
+        }
+    }
+
+    /// An in-process plugin used to exercise the registry without a shared object.
+    #[derive(Debug)]
+    struct TestPlugin {
+        name: &'static str,
+        marker: u32,
+    }
+
+    impl Plugin for TestPlugin {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn execute(
+            &self,
+            _context: &dyn Any,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+    }
+
+    fn test_plugin(name: &'static str, marker: u32) -> Box<dyn Plugin> {
+        Box::new(TestPlugin { name, marker })
+    }
+
+    fn make_file_path(module_name: &str) -> String {
+        let mut path_name = PathBuf::new();
+        let mut module_name_prefix = String::from(std::env::consts::DLL_PREFIX);
+        module_name_prefix.push_str(module_name);
+        path_name.push("..");
+        path_name.push("target");
+        path_name.push("release");
+        path_name.push(module_name_prefix);
+        path_name.set_extension(std::env::consts::DLL_EXTENSION);
+        path_name.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn get_plugin_path_test() {
+        set_env_var();
+        let plugin_manager = PluginManager::new();
+        let metadata = plugin_manager.get_plugin_metadata().unwrap();
+        let plugins = metadata.plugins;
+        match plugins {
+            Some(plug_entry) => {
+                for (group, entry) in plug_entry {
+                    match entry {
+                        PluginEntry::Individual(path) => {
+                            assert!(Path::new(&path).is_absolute());
+                            assert_eq!(
+                                std::fs::canonicalize(path).unwrap(),
+                                std::fs::canonicalize(make_file_path("plugin_mods")).unwrap()
+                            );
+                        }
+                        PluginEntry::Group(path) => {
+                            path.iter().for_each(|(metadata_name, path)| {
+                                assert_eq!(
+                                    std::fs::canonicalize(path).unwrap(),
+                                    std::fs::canonicalize(make_file_path("plugin_inventory"))
+                                        .unwrap()
+                                );
+                                assert_eq!(metadata_name, "inventory_a");
+                                assert_eq!(group, "inventory");
+                            });
+                        }
+                        PluginEntry::Detailed(spec) => {
+                            panic!("Unexpected detailed entry: {spec:?}");
+                        }
+                    }
+                }
+            }
+            None => {
+                panic!("No plugins found in metadata");
+            }
+        }
+    }
+
+    #[test]
+    fn activate_plugins_test() {
+        set_env_var();
+        let mut plugin_manager = PluginManager::new();
+        plugin_manager = plugin_manager.activate_plugins().unwrap();
+        assert!(plugin_manager.get_plugin("plugin_a").is_some());
+        assert_eq!(plugin_manager.plugins.len(), 3);
+    }
+
+    #[test]
+    /// Test for duplicate activation of plugins.
+    fn activate_plugins_duplicate_test() {
+        set_env_var();
+        let plugin_manager = PluginManager::new().activate_plugins().unwrap();
+        let result = plugin_manager.activate_plugins();
+        assert!(matches!(
+            result,
+            Err(PluginError::DuplicatePlugin { name, .. }) if name == "plugin_a"
+        ));
+    }
+
+    #[test]
+    fn load_plugin_test() {
+        let plugin_manager = PluginManager::new();
+        let filename = make_file_path("plugin_mods");
+        let (_library, plugins) = plugin_manager.load_plugin(&filename).unwrap();
+        assert_eq!(plugins.len(), 2);
+        assert_eq!(plugins[0].name(), "plugin_a");
+    }
+
+    #[test]
+    fn load_plugin_and_panic_test() {
+        let plugin_manager = PluginManager::new();
+        let filename = make_file_path("plugin_mods");
+        let (_library, _) = plugin_manager.load_plugin(&filename).unwrap();
+        let filename = make_file_path("plugin_mods");
+        let (_library, plugins) = plugin_manager.load_plugin(&filename).unwrap();
+        assert_eq!(plugins.len(), 2);
+        assert_eq!(plugins[0].name(), "plugin_a");
+    }
+
+    #[test]
+    fn activate_plugins_with_groups_test() {
+        set_env_var();
+        let plugin_manager = PluginManager::new().activate_plugins().unwrap();
+
+        // Check individual plugin
+        let plugin_a = plugin_manager.get_plugin("plugin_a").unwrap();
+        assert_eq!(plugin_a.group, None);
+
+        // Check grouped plugin
+        let inventory_plugin = plugin_manager.get_plugin("inventory_a").unwrap();
+        assert_eq!(inventory_plugin.group, Some("inventory".to_string()));
+
+        // Get all plugins in the "inventory" group
+        let inventory_plugins = plugin_manager.get_plugins_by_group("inventory");
+        assert_eq!(inventory_plugins.len(), 1);
+        assert_eq!(inventory_plugins[0].plugin.name(), "inventory_a");
+
+        assert_eq!(plugin_manager.plugins.len(), 3);
+    }
+
+    #[test]
+    fn get_all_plugin_names_and_groups_test() {
+        set_env_var();
+        let plugin_manager = PluginManager::new().activate_plugins().unwrap();
+        let all_plugins = plugin_manager.get_all_plugin_names_and_groups();
+        assert_eq!(all_plugins.len(), 3);
+        all_plugins
+            .iter()
+            .for_each(|(name, group)| match name.as_str() {
+                "plugin_a" => assert_eq!(group.as_deref(), None),
+                "plugin_b" => assert_eq!(group.as_deref(), None),
+                "inventory_a" => assert_eq!(group.as_deref(), Some("inventory")),
+                _ => panic!("Unexpected plugin name"),
+            });
+    }
+
+    #[test]
+    fn deregister_plugin_test() {
+        set_env_var();
+        let mut plugin_manager = PluginManager::new().activate_plugins().unwrap();
+        assert_eq!(plugin_manager.plugins.len(), 3);
+
+        // Deregister individual plugin
+        let plugin_name = plugin_manager.deregister_plugin("plugin_a").unwrap();
+        assert_eq!(plugin_name, "plugin_a");
+        assert_eq!(plugin_manager.plugins.len(), 2);
+
+        // Deregister grouped plugin
+        let plugin_name = plugin_manager.deregister_plugin("inventory_a").unwrap();
+        assert_eq!(plugin_name, "inventory_a");
+        assert_eq!(plugin_manager.plugins.len(), 1);
+
+        // Deregister non-existent plugin
+        let result = plugin_manager.deregister_plugin("non_existent_plugin");
+        assert!(matches!(result, Err(PluginError::NotFound { .. })));
+    }
+
+    #[test]
+    fn deregister_all_plugins_test() {
+        set_env_var();
+        let mut plugin_manager = PluginManager::new().activate_plugins().unwrap();
+        assert_eq!(plugin_manager.plugins.len(), 3);
+
+        // Deregister all plugins
+        let num_plugins_deregistered = plugin_manager.deregister_all_plugins();
+        assert_eq!(num_plugins_deregistered.len(), 3);
+        assert_eq!(plugin_manager.plugins.len(), 0);
+    }
+
+    #[test]
+    fn load_plugin_missing_file_test() {
+        let plugin_manager = PluginManager::new();
+        let result = plugin_manager.load_plugin("../target/release/does_not_exist.so");
+        assert!(matches!(result, Err(PluginError::FileNotFound { .. })));
+    }
+
+    #[test]
+    fn typed_errors_test() {
+        set_env_var();
+        let plugin_manager = PluginManager::new().activate_plugins().unwrap();
+
+        let result = plugin_manager.execute_plugin("non_existent_plugin", &());
+        assert!(
+            matches!(result, Err(PluginError::NotFound { name }) if name == "non_existent_plugin")
+        );
+
+        let result = plugin_manager.with_any::<String>("plugin_a");
+        assert!(matches!(result, Err(PluginError::Downcast { name, .. }) if name == "plugin_a"));
+    }
+
+    #[test]
+    fn register_plugin_conflict_policy_test() {
+        let mut plugin_manager = PluginManager::new();
+        plugin_manager
+            .register_plugin(test_plugin("dup", 1), Some("first".to_string()))
+            .unwrap();
+        let result = plugin_manager.register_plugin(test_plugin("dup", 2), None);
+        assert!(matches!(result, Err(PluginError::DuplicatePlugin { name, .. }) if name == "dup"));
+
+        let mut plugin_manager = plugin_manager.with_conflict_policy(ConflictPolicy::KeepFirst);
+        plugin_manager
+            .register_plugin(test_plugin("dup", 2), None)
+            .unwrap();
+        assert_eq!(
+            plugin_manager.with_any::<TestPlugin>("dup").unwrap().marker,
+            1
+        );
+
+        let mut plugin_manager =
+            plugin_manager.with_conflict_policy(ConflictPolicy::ReplaceExisting);
+        plugin_manager
+            .register_plugin(test_plugin("dup", 3), None)
+            .unwrap();
+        assert_eq!(
+            plugin_manager.with_any::<TestPlugin>("dup").unwrap().marker,
+            3
+        );
+
+        let mut plugin_manager =
+            plugin_manager.with_conflict_policy(ConflictPolicy::RenameWithGroupPrefix);
+        plugin_manager
+            .register_plugin(test_plugin("dup", 4), Some("second".to_string()))
+            .unwrap();
+        assert_eq!(
+            plugin_manager
+                .with_any::<TestPlugin>("second::dup")
+                .unwrap()
+                .marker,
+            4
+        );
+        let result = plugin_manager.register_plugin(test_plugin("dup", 5), None);
+        assert!(matches!(result, Err(PluginError::DuplicatePlugin { .. })));
+        assert_eq!(plugin_manager.plugins.len(), 2);
+    }
+
+    #[test]
+    fn activate_plugins_twice_keep_first_test() {
+        set_env_var();
+        let plugin_manager = PluginManager::new()
+            .with_conflict_policy(ConflictPolicy::KeepFirst)
+            .activate_plugins()
+            .unwrap()
+            .activate_plugins()
+            .unwrap();
+        assert_eq!(plugin_manager.plugins.len(), 3);
+    }
+
+    #[test]
+    fn activation_report_conflict_test() {
+        set_env_var();
+        let tasks_twice = || {
+            IndexMap::from([
+                (
+                    "tasks".to_string(),
+                    PluginEntry::Group(IndexMap::from([(
+                        "task_a".to_string(),
+                        make_file_path("plugin_tasks"),
+                    )])),
+                ),
+                (
+                    "more_tasks".to_string(),
+                    PluginEntry::Group(IndexMap::from([(
+                        "task_a".to_string(),
+                        make_file_path("plugin_tasks"),
+                    )])),
+                ),
+            ])
+        };
+
+        let mut plugin_manager =
+            PluginManager::new().with_conflict_policy(ConflictPolicy::KeepFirst);
+        plugin_manager.plugin_path.push(tasks_twice());
+        let (plugin_manager, report) = plugin_manager.activate_available_plugins().unwrap();
+        assert!(report.is_success());
+        assert_eq!(
+            report.activated.iter().collect::<Vec<_>>(),
+            plugin_manager.get_all_plugin_names()
+        );
+
+        let mut plugin_manager =
+            PluginManager::new().with_conflict_policy(ConflictPolicy::RenameWithGroupPrefix);
+        plugin_manager.plugin_path.push(tasks_twice());
+        let (plugin_manager, report) = plugin_manager.activate_available_plugins().unwrap();
+        assert_eq!(
+            report.activated.iter().collect::<Vec<_>>(),
+            plugin_manager.get_all_plugin_names()
+        );
+        assert!(report.activated.contains(&"more_tasks::task_a".to_string()));
+    }
+
+    #[test]
+    fn activate_available_plugins_test() {
+        set_env_var();
+        let mut plugin_manager = PluginManager::new();
+        plugin_manager.plugin_path.push(IndexMap::from([(
+            "tasks".to_string(),
+            PluginEntry::Group(IndexMap::from([
+                ("task_a".to_string(), make_file_path("plugin_tasks")),
+                ("missing".to_string(), make_file_path("plugin_missing")),
+            ])),
+        )]));
+
+        let (plugin_manager, report) = plugin_manager.activate_available_plugins().unwrap();
+        assert!(!report.is_success());
+        assert_eq!(report.failures.len(), 1);
+        let failure = &report.failures[0];
+        assert_eq!(failure.entry, "missing");
+        assert_eq!(failure.group.as_deref(), Some("tasks"));
+        assert!(matches!(failure.error, PluginError::FileNotFound { .. }));
+
+        assert_eq!(report.activated.len(), 4);
+        assert_eq!(plugin_manager.plugins.len(), 4);
+        let task_a = plugin_manager.get_plugin("task_a").unwrap();
+        assert_eq!(task_a.group.as_deref(), Some("tasks"));
+    }
+
+    #[test]
+    fn activate_plugins_group_error_test() {
+        set_env_var();
+        let mut plugin_manager = PluginManager::new();
+        plugin_manager.plugin_path.push(IndexMap::from([(
+            "tasks".to_string(),
+            PluginEntry::Group(IndexMap::from([(
+                "missing".to_string(),
+                make_file_path("plugin_missing"),
+            )])),
+        )]));
+        let result = plugin_manager.activate_plugins();
+        assert!(matches!(result, Err(PluginError::FileNotFound { .. })));
+    }
+
+    #[test]
+    fn unload_library_test() {
+        set_env_var();
+        let mut plugin_manager = PluginManager::new().activate_plugins().unwrap();
+        let plugin_a = plugin_manager.get_plugin("plugin_a").unwrap();
+        assert_eq!(
+            plugin_a.descriptor.path,
+            Some(std::fs::canonicalize(make_file_path("plugin_mods")).unwrap())
+        );
+
+        let mut unloaded = plugin_manager
+            .unload_library(&make_file_path("plugin_mods"))
+            .unwrap();
+        unloaded.sort();
+        assert_eq!(unloaded, vec!["plugin_a", "plugin_b"]);
+        assert_eq!(plugin_manager.plugins.len(), 1);
+        assert!(plugin_manager.get_plugin("inventory_a").is_some());
+
+        let result = plugin_manager.unload_library(&make_file_path("plugin_mods"));
+        assert!(matches!(result, Err(PluginError::LibraryNotLoaded { .. })));
+    }
+
+    #[derive(Debug, Plugin)]
+    #[plugin(group = "derived", version = "1.2.0")]
+    struct DerivedPlugin;
+
+    impl Execute for DerivedPlugin {
+        fn execute(
+            &self,
+            context: &dyn Any,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            match context.downcast_ref::<&str>() {
+                Some(_) => Ok(()),
+                None => Err("expected a &str context".into()),
+            }
+        }
+    }
+
+    #[test]
+    fn derive_plugin_test() {
+        assert_eq!(DerivedPlugin.name(), "derived_plugin");
+        assert_eq!(DerivedPlugin.version().as_deref(), Some("1.2.0"));
+
+        let mut plugin_manager = PluginManager::new();
+        plugin_manager
+            .register_plugin(Box::new(DerivedPlugin), None)
+            .unwrap();
+        let plugin_info = plugin_manager.get_plugin("derived_plugin").unwrap();
+        assert_eq!(plugin_info.group.as_deref(), Some("derived"));
+        assert!(
+            plugin_manager
+                .with_any::<DerivedPlugin>("derived_plugin")
+                .is_ok()
+        );
+
+        assert!(
+            plugin_manager
+                .execute_plugin("derived_plugin", &"ok")
+                .is_ok()
+        );
+        let result = plugin_manager.execute_plugin("derived_plugin", &());
+        assert!(matches!(result, Err(PluginError::Execution { .. })));
+    }
+
+    #[test]
+    fn with_path_individual_test() {
+        set_env_var();
+        let plugin_manager = PluginManager::new()
+            .with_path(&make_file_path("plugin_tasks"), None)
+            .unwrap();
+        let entry = &plugin_manager.plugin_path[0];
+        let stem = format!("{}plugin_tasks", std::env::consts::DLL_PREFIX);
+        assert!(matches!(
+            entry.get(&stem),
+            Some(PluginEntry::Individual(path)) if *path == make_file_path("plugin_tasks")
+        ));
+
+        let plugin_manager = plugin_manager.activate_plugins().unwrap();
+        assert_eq!(plugin_manager.plugins.len(), 4);
+        let task_a = plugin_manager.get_plugin("task_a").unwrap();
+        assert_eq!(task_a.group, None);
+    }
+
+    #[test]
+    fn with_path_group_test() {
+        set_env_var();
+        let plugin_manager = PluginManager::new()
+            .with_path(&make_file_path("plugin_tasks"), Some("tasks"))
+            .unwrap();
+        match plugin_manager.plugin_path[0].get("tasks") {
+            Some(PluginEntry::Group(group)) => {
+                assert_eq!(group.len(), 1);
+                assert!(
+                    group
+                        .values()
+                        .all(|path| *path == make_file_path("plugin_tasks"))
+                );
+            }
+            other => panic!("Unexpected entry: {other:?}"),
+        }
+
+        let plugin_manager = plugin_manager.activate_plugins().unwrap();
+        let task_plugins = plugin_manager.get_plugins_by_group("tasks");
+        assert_eq!(task_plugins.len(), 1);
+        assert_eq!(task_plugins[0].plugin.name(), "task_a");
+    }
+
+    #[test]
+    fn with_path_missing_file_test() {
+        let result = PluginManager::new().with_path(&make_file_path("plugin_missing"), None);
+        assert!(matches!(result, Err(PluginError::FileNotFound { .. })));
+    }
+
+    #[test]
+    fn with_plugin_dir_test() {
+        set_env_var();
+        let plugin_manager = PluginManager::new()
+            .with_plugin_dir_options(
+                PluginDir::new("../target/release")
+                    .pattern("*plugin_tasks*")
+                    .group("tasks"),
+            )
+            .unwrap()
+            .activate_plugins()
+            .unwrap();
+        assert_eq!(plugin_manager.plugins.len(), 4);
+        let task_plugins = plugin_manager.get_plugins_by_group("tasks");
+        assert_eq!(task_plugins.len(), 1);
+        assert_eq!(task_plugins[0].plugin.name(), "task_a");
+
+        let result = PluginManager::new().with_plugin_dir("../target/does_not_exist", None);
+        assert!(matches!(result, Err(PluginError::FileNotFound { .. })));
+    }
+
+    #[test]
+    fn unreadable_plugin_dir_test() {
+        set_env_var();
+        let new_plugin_manager = || {
+            let mut plugin_manager = PluginManager::new()
+                .with_path(&make_file_path("plugin_tasks"), Some("tasks"))
+                .unwrap();
+            // The directory is removed after it was added.
+            plugin_manager
+                .plugin_dirs
+                .push(PluginDir::new("../target/does_not_exist").group("gone"));
+            plugin_manager
+        };
+
+        let (plugin_manager, report) = new_plugin_manager().activate_available_plugins().unwrap();
+        assert_eq!(report.failures.len(), 1);
+        let failure = &report.failures[0];
+        assert_eq!(failure.path, "../target/does_not_exist");
+        assert_eq!(failure.group.as_deref(), Some("gone"));
+        assert!(matches!(failure.error, PluginError::DirectoryScan { .. }));
+        assert!(plugin_manager.get_plugin("task_a").is_some());
+
+        let result = new_plugin_manager().activate_plugins();
+        assert!(matches!(result, Err(PluginError::DirectoryScan { .. })));
+    }
+
+    #[test]
+    fn inline_config_source_test() {
+        let config = format!(
+            "[plugins.tasks]\ntask_a = {:?}\n",
+            make_file_path("plugin_tasks")
+        );
+        let plugin_manager = PluginManager::new()
+            .with_config_source(PluginConfigSource::Inline(config))
+            .activate_plugins()
+            .unwrap();
+        assert_eq!(plugin_manager.plugins.len(), 1);
+        let task_a = plugin_manager.get_plugin("task_a").unwrap();
+        assert_eq!(task_a.group.as_deref(), Some("tasks"));
+    }
+
+    #[test]
+    fn file_config_source_test() {
+        let plugin_manager = PluginManager::new()
+            .with_config_source(PluginConfigSource::File(manifest_file().into()))
+            .activate_plugins()
+            .unwrap();
+        assert_eq!(plugin_manager.plugins.len(), 3);
+    }
+
+    #[test]
+    fn relative_paths_resolved_against_manifest_test() {
+        let manifest = std::fs::canonicalize(manifest_file()).unwrap();
+        let plugin_manager = PluginManager::new()
+            .with_config_source(PluginConfigSource::File(manifest))
+            .activate_plugins()
+            .unwrap();
+        assert_eq!(plugin_manager.plugins.len(), 3);
+
+        let config = "[plugins]\nmissing = \"plugins/libmissing.so\"\n".to_string();
+        let result = PluginManager::new()
+            .with_config_source(PluginConfigSource::Inline(config))
+            .activate_plugins();
+        let expected = std::env::current_dir()
+            .unwrap()
+            .join("plugins")
+            .join("libmissing.so");
+        assert!(matches!(result, Err(PluginError::FileNotFound { path }) if path == expected));
+    }
+
+    #[test]
+    fn detailed_plugin_entry_test() {
+        let config = format!(
+            r#"
+            [plugins]
+            tasks = {{ path = {tasks:?}, group = "tasks", priority = 10, config = {{ retries = 3 }} }}
+            disabled = {{ path = "plugins/libmissing.so", enabled = false }}
+            mods = {mods:?}
+            "#,
+            tasks = make_file_path("plugin_tasks"),
+            mods = make_file_path("plugin_mods"),
+        );
+        let plugin_manager =
+            PluginManager::new().with_config_source(PluginConfigSource::Inline(config));
+
+        let registrations = plugin_manager
+            .collect_registrations(true, &mut ActivationReport::default())
+            .unwrap();
+        let names: Vec<&str> = registrations
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, vec!["tasks", "mods"]);
+        match &registrations[0].1 {
+            PluginEntry::Detailed(spec) => {
+                assert_eq!(spec.priority, 10);
+                assert!(spec.enabled);
+                assert_eq!(
+                    spec.config.as_ref().unwrap()["retries"].as_integer(),
+                    Some(3)
+                );
+            }
+            other => panic!("Unexpected entry: {other:?}"),
+        }
+
+        let plugin_manager = plugin_manager.activate_plugins().unwrap();
+        assert_eq!(plugin_manager.plugins.len(), 3);
+        let task_a = plugin_manager.get_plugin("task_a").unwrap();
+        assert_eq!(task_a.group.as_deref(), Some("tasks"));
+    }
+
+    #[derive(Debug, Default, Plugin)]
+    #[plugin(configure)]
+    struct ConfiguredPlugin {
+        retries: i64,
+    }
+
+    impl Configure for ConfiguredPlugin {
+        fn configure(
+            &mut self,
+            config: &toml::Value,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.retries = config
+                .get("retries")
+                .and_then(toml::Value::as_integer)
+                .ok_or("retries must be an integer")?;
+            Ok(())
+        }
+    }
+
+    impl Execute for ConfiguredPlugin {
+        fn execute(
+            &self,
+            _context: &dyn Any,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn configure_plugin_test() {
+        let mut plugin = ConfiguredPlugin::default();
+        PluginManager::configure_plugin(&mut plugin, None).unwrap();
+        assert_eq!(plugin.retries, 0);
+
+        let config: toml::Table = toml::from_str("retries = 3").unwrap();
+        PluginManager::configure_plugin(&mut plugin, Some(&config)).unwrap();
+        assert_eq!(plugin.retries, 3);
+
+        let config: toml::Table = toml::from_str("retries = \"many\"").unwrap();
+        let result = PluginManager::configure_plugin(&mut plugin, Some(&config));
+        assert!(
+            matches!(result, Err(PluginError::Configuration { name, .. }) if name == "configured_plugin")
+        );
+
+        // Plugins that do not override `configure` accept any settings.
+        let mut plugin = TestPlugin {
+            name: "test_plugin",
+            marker: 0,
+        };
+        assert!(PluginManager::configure_plugin(&mut plugin, Some(&config)).is_ok());
+    }
+
+    #[derive(Default, Plugin)]
+    #[plugin(lifecycle)]
+    struct LifecyclePlugin {
+        events: Arc<std::sync::Mutex<Vec<&'static str>>>,
+        fail_load: bool,
+        fail_unload: bool,
+    }
+
+    impl Lifecycle for LifecyclePlugin {
+        fn on_load(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.events.lock().unwrap().push("on_load");
+            if self.fail_load {
+                return Err("cannot open resources".into());
+            }
+            Ok(())
+        }
+
+        fn on_activate(&self) {
+            self.events.lock().unwrap().push("on_activate");
+        }
+
+        fn on_deactivate(&self) {
+            self.events.lock().unwrap().push("on_deactivate");
+        }
+
+        fn on_unload(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.events.lock().unwrap().push("on_unload");
+            if self.fail_unload {
+                return Err("cannot flush resources".into());
+            }
+            Ok(())
+        }
+    }
+
+    impl Execute for LifecyclePlugin {
+        fn execute(
+            &self,
+            _context: &dyn Any,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn lifecycle_hooks_test() {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut plugin_manager = PluginManager::new();
+        plugin_manager
+            .register_plugin(
+                Box::new(LifecyclePlugin {
+                    events: events.clone(),
+                    ..Default::default()
+                }),
+                None,
+            )
+            .unwrap();
+        assert_eq!(*events.lock().unwrap(), ["on_load", "on_activate"]);
+
+        assert!(plugin_manager.deregister_plugin("lifecycle_plugin").is_ok());
+        assert_eq!(
+            *events.lock().unwrap(),
+            ["on_load", "on_activate", "on_deactivate", "on_unload"]
+        );
+        assert!(plugin_manager.take_lifecycle_errors().is_empty());
+
+        events.lock().unwrap().clear();
+        let result = plugin_manager.register_plugin(
+            Box::new(LifecyclePlugin {
+                events: events.clone(),
+                fail_load: true,
+                ..Default::default()
+            }),
+            None,
+        );
+        assert!(matches!(
+            result,
+            Err(PluginError::Lifecycle {
+                hook: "on_load",
+                ..
+            })
+        ));
+        assert!(plugin_manager.get_plugin("lifecycle_plugin").is_none());
+        assert_eq!(*events.lock().unwrap(), ["on_load"]);
+
+        events.lock().unwrap().clear();
+        plugin_manager
+            .register_plugin(
+                Box::new(LifecyclePlugin {
+                    events: events.clone(),
+                    fail_unload: true,
+                    ..Default::default()
+                }),
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            plugin_manager.deregister_all_plugins(),
+            ["lifecycle_plugin"]
+        );
+        assert!(plugin_manager.plugins.is_empty());
+        let errors = plugin_manager.take_lifecycle_errors();
+        assert!(matches!(
+            errors.as_slice(),
+            [PluginError::Lifecycle {
+                hook: "on_unload",
+                ..
+            }]
+        ));
+
+        events.lock().unwrap().clear();
+        plugin_manager
+            .register_plugin(
+                Box::new(LifecyclePlugin {
+                    events: events.clone(),
+                    ..Default::default()
+                }),
+                None,
+            )
+            .unwrap();
+        drop(plugin_manager);
+        assert_eq!(
+            *events.lock().unwrap(),
+            ["on_load", "on_activate", "on_deactivate", "on_unload"]
+        );
+    }
+
+    #[derive(Debug, Plugin)]
+    #[plugin(depends_on("test_plugin"))]
+    struct DependentPlugin;
+
+    impl Execute for DependentPlugin {
+        fn execute(
+            &self,
+            _context: &dyn Any,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn register_plugin_dependencies_test() {
+        assert_eq!(DependentPlugin.depends_on(), ["test_plugin"]);
+
+        let mut plugin_manager = PluginManager::new();
+        let result = plugin_manager.register_plugin(Box::new(DependentPlugin), None);
+        assert!(matches!(
+            result,
+            Err(PluginError::MissingDependency { name, dependency })
+                if name == "dependent_plugin" && dependency == "test_plugin"
+        ));
+
+        plugin_manager
+            .register_plugin(test_plugin("test_plugin", 1), None)
+            .unwrap();
+        plugin_manager
+            .register_plugin(Box::new(DependentPlugin), None)
+            .unwrap();
+        assert_eq!(
+            plugin_manager.dependents("test_plugin"),
+            ["dependent_plugin"]
+        );
+
+        let result = plugin_manager.deregister_plugin("test_plugin");
+        assert!(matches!(
+            result,
+            Err(PluginError::HasDependents { dependents, .. }) if dependents == ["dependent_plugin"]
+        ));
+        assert_eq!(
+            plugin_manager.deregister_all_plugins(),
+            ["dependent_plugin", "test_plugin"]
+        );
+
+        plugin_manager
+            .register_plugin(test_plugin("test_plugin", 1), None)
+            .unwrap();
+        plugin_manager
+            .register_plugin(Box::new(DependentPlugin), None)
+            .unwrap();
+        assert_eq!(
+            plugin_manager
+                .force_deregister_plugin("test_plugin")
+                .unwrap(),
+            "test_plugin"
+        );
+        assert!(plugin_manager.get_plugin("dependent_plugin").is_some());
     }
 
-    pub fn with_path(mut self, path: &str, group: Option<&str>) -> Result<Self, Error> {
-        let path = Path::new(&path);
-        if path.exists() {
-            let path_string = if let Some(path_str) = path.to_str() {
-                path_str.to_string()
-            } else {
-                return Err(Error::new(
-                    ErrorKind::InvalidData,
-                    "Path contains invalid Unicode",
-                ));
-            };
-            if let Some(group_string) = group {
-                let group_info = HashMap::from([(
-                    group_string.to_string(),
-                    PluginEntry::Group(HashMap::from([(path_string, group_string.to_string())])),
-                )]);
-                self.plugin_path.push(group_info);
-            } else {
-                todo!()
-                // TODO: implement individual plugin registration
-                // let individual_info = PluginEntry::Individual(path_string);
-                // self.plugin_path.push(PluginEntry::Individual(path_string));
-            };
-            Ok(self)
-        } else {
-            Err(Error::new(
-                ErrorKind::NotFound,
-                format!("FileNotFoundError: {:?}", path.as_os_str()),
-            ))
+    #[test]
+    fn activation_dependency_order_test() {
+        // The inventory entry has the higher priority, but must wait for task_a.
+        let config = format!(
+            r#"
+            [plugins]
+            inventory = {{ path = {inventory:?}, priority = 10, depends_on = ["task_a"] }}
+            tasks = {tasks:?}
+            "#,
+            inventory = make_file_path("plugin_inventory"),
+            tasks = make_file_path("plugin_tasks"),
+        );
+        let plugin_manager = PluginManager::new()
+            .with_config_source(PluginConfigSource::Inline(config))
+            .activate_plugins()
+            .unwrap();
+        let inventory_a = plugin_manager.get_plugin("inventory_a").unwrap();
+        assert_eq!(inventory_a.depends_on, ["task_a"]);
+
+        let config = format!(
+            "[plugins]\ninventory = {{ path = {:?}, depends_on = [\"task_a\"] }}\n",
+            make_file_path("plugin_inventory"),
+        );
+        let result = PluginManager::new()
+            .with_config_source(PluginConfigSource::Inline(config))
+            .activate_plugins();
+        assert!(matches!(
+            result,
+            Err(PluginError::MissingDependency { name, dependency })
+                if name == "inventory_a" && dependency == "task_a"
+        ));
+
+        let config = format!(
+            r#"
+            [plugins]
+            inventory = {{ path = {inventory:?}, depends_on = ["task_a"] }}
+            tasks = {{ path = {tasks:?}, depends_on = ["inventory_a"] }}
+            mods = {{ path = {mods:?}, depends_on = ["task_a"] }}
+            "#,
+            inventory = make_file_path("plugin_inventory"),
+            tasks = make_file_path("plugin_tasks"),
+            mods = make_file_path("plugin_mods"),
+        );
+        let (plugin_manager, report) = PluginManager::new()
+            .with_config_source(PluginConfigSource::Inline(config))
+            .activate_available_plugins()
+            .unwrap();
+        assert!(plugin_manager.plugins.is_empty());
+        assert_eq!(report.failures.len(), 4);
+        for failure in &report.failures[..2] {
+            assert!(matches!(
+                &failure.error,
+                PluginError::DependencyCycle { cycle, .. } if *cycle == ["inventory_a", "task_a"]
+            ));
+        }
+        for failure in &report.failures[2..] {
+            assert!(matches!(
+                &failure.error,
+                PluginError::MissingDependency { dependency, .. } if dependency == "task_a"
+            ));
         }
     }
 
-    /// Gets the plugin with the given name.
-    pub fn get_plugin(&self, name: &str) -> Option<&PluginInfo> {
-        self.plugins.get(name)
+    #[test]
+    fn renamed_dependency_test() {
+        let config = format!(
+            r#"
+            [plugins]
+            tasks = {{ path = {tasks:?}, group = "tasks" }}
+            more_tasks = {{ path = {tasks:?}, group = "more_tasks" }}
+            inventory = {{ path = {inventory:?}, depends_on = ["task_a"] }}
+            "#,
+            tasks = make_file_path("plugin_tasks"),
+            inventory = make_file_path("plugin_inventory"),
+        );
+        let mut plugin_manager = PluginManager::new()
+            .with_config_source(PluginConfigSource::Inline(config))
+            .with_conflict_policy(ConflictPolicy::RenameWithGroupPrefix)
+            .activate_plugins()
+            .unwrap();
+        assert_eq!(
+            plugin_manager.get_all_plugin_names(),
+            ["task_a", "more_tasks::task_a", "inventory_a"]
+        );
+        assert_eq!(
+            plugin_manager.dependents("more_tasks::task_a"),
+            ["inventory_a"]
+        );
+        assert!(matches!(
+            plugin_manager.deregister_plugin("task_a"),
+            Err(PluginError::HasDependents { .. })
+        ));
     }
 
-    pub fn get_plugins_by_group(&self, group: &str) -> Vec<&PluginInfo> {
-        self.plugins
-            .values()
-            .filter(|plugin_info| plugin_info.group.as_deref() == Some(group))
-            .collect()
+    #[test]
+    fn deterministic_order_test() {
+        set_env_var();
+        let plugin_manager = PluginManager::new().activate_plugins().unwrap();
+        assert_eq!(
+            plugin_manager.get_all_plugin_names(),
+            ["plugin_a", "plugin_b", "inventory_a"]
+        );
+
+        let config = format!(
+            r#"
+            [plugins]
+            mods = {mods:?}
+            inventory = {{ path = {inventory:?}, group = "inventory" }}
+            tasks = {{ path = {tasks:?}, group = "inventory", priority = 1 }}
+            "#,
+            mods = make_file_path("plugin_mods"),
+            inventory = make_file_path("plugin_inventory"),
+            tasks = make_file_path("plugin_tasks"),
+        );
+        let mut plugin_manager = PluginManager::new()
+            .with_config_source(PluginConfigSource::Inline(config))
+            .activate_plugins()
+            .unwrap();
+        assert_eq!(
+            plugin_manager.get_all_plugin_names_and_groups(),
+            [
+                ("task_a".to_string(), Some("inventory".to_string())),
+                ("plugin_a".to_string(), None),
+                ("plugin_b".to_string(), None),
+                ("inventory_a".to_string(), Some("inventory".to_string())),
+            ]
+        );
+        let group: Vec<String> = plugin_manager
+            .get_plugins_by_group("inventory")
+            .iter()
+            .map(|plugin_info| plugin_info.plugin.name())
+            .collect();
+        assert_eq!(group, ["task_a", "inventory_a"]);
+
+        plugin_manager.deregister_plugin("plugin_a").unwrap();
+        assert_eq!(
+            plugin_manager.get_all_plugin_names(),
+            ["task_a", "plugin_b", "inventory_a"]
+        );
+        assert_eq!(
+            plugin_manager.deregister_all_plugins(),
+            ["inventory_a", "plugin_b", "task_a"]
+        );
     }
 
-    /// Gets all the **names** of the registered plugins.
-    pub fn get_all_plugin_names(&self) -> Vec<&String> {
-        self.plugins.keys().collect()
+    #[test]
+    fn version_requirement_test() {
+        let plugin_manager = PluginManager::new()
+            .with_config_source(PluginConfigSource::Inline(format!(
+                "[plugins]\ntasks = {{ path = {:?}, version = \"^0.1\" }}\n",
+                make_file_path("plugin_tasks"),
+            )))
+            .activate_plugins()
+            .unwrap();
+        let task_a = plugin_manager.get_plugin("task_a").unwrap();
+        assert_eq!(task_a.descriptor.version, Some(Version::new(0, 1, 0)));
+
+        let (_, report) = PluginManager::new()
+            .with_config_source(PluginConfigSource::Inline(format!(
+                "[plugins]\ntasks = {{ path = {:?}, version = \">=1.2\" }}\n",
+                make_file_path("plugin_tasks"),
+            )))
+            .activate_available_plugins()
+            .unwrap();
+        assert!(report.activated.is_empty());
+        let error = &report.failures[0].error;
+        assert!(matches!(
+            error,
+            PluginError::VersionMismatch { name, found: Some(found), .. }
+                if name == "task_a" && *found == Version::new(0, 1, 0)
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Plugin 'task_a' version 0.1.0 does not satisfy the requirement >=1.2"
+        );
+
+        let result = PluginManager::new()
+            .with_config_source(PluginConfigSource::Inline(format!(
+                "[plugins]\ntasks = {{ path = {:?}, version = \"not a requirement\" }}\n",
+                make_file_path("plugin_tasks"),
+            )))
+            .activate_plugins();
+        assert!(result.is_err());
+
+        let result = PluginManager::plugin_descriptor(
+            &TestPlugin {
+                name: "test_plugin",
+                marker: 0,
+            },
+            Some(&VersionReq::parse("^1").unwrap()),
+        );
+        assert!(matches!(
+            result,
+            Err(PluginError::VersionMismatch { found: None, .. })
+        ));
     }
 
-    /// Gets all the **names** and **groups** of the registered plugins.
-    pub fn get_all_plugin_names_and_groups(&self) -> Vec<(String, Option<String>)> {
-        self.plugins
-            .iter()
-            .map(|(name, plugin_info)| (name.clone(), plugin_info.group.clone()))
-            .collect()
+    #[test]
+    fn find_by_tag_and_capability_test() {
+        set_env_var();
+        let plugin_manager = PluginManager::new().activate_plugins().unwrap();
+        let names = |plugins: Vec<&PluginInfo>| -> Vec<String> {
+            plugins
+                .iter()
+                .map(|plugin_info| plugin_info.descriptor.name.clone())
+                .collect()
+        };
+        assert_eq!(
+            names(plugin_manager.find_by_tag("mods")),
+            ["plugin_a", "plugin_b"]
+        );
+        assert_eq!(
+            names(plugin_manager.find_by_capability("export-csv")),
+            ["inventory_a"]
+        );
+        assert!(plugin_manager.find_by_tag("missing").is_empty());
+
+        let inventory_a = &plugin_manager.get_plugin("inventory_a").unwrap().descriptor;
+        assert_eq!(
+            inventory_a.description.as_deref(),
+            Some("Collects the inventory")
+        );
+        assert_eq!(
+            inventory_a.path,
+            Some(std::fs::canonicalize(make_file_path("plugin_inventory")).unwrap())
+        );
+        let hash = inventory_a.library_hash.as_deref().unwrap();
+        assert_eq!(hash.len(), 64);
+        assert_eq!(
+            plugin_manager
+                .get_plugin("plugin_a")
+                .unwrap()
+                .descriptor
+                .library_hash,
+            plugin_manager
+                .get_plugin("plugin_b")
+                .unwrap()
+                .descriptor
+                .library_hash
+        );
     }
 
-    pub fn execute_plugin(
-        &self,
-        name: &str,
-        context: &dyn Any,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(plugin_info) = self.get_plugin(name) {
-            plugin_info.plugin.execute(context)
-        } else {
-            let msg = format!("Plugin '{}' not found", name);
-            log::error!("{msg}");
-            Err(msg.into())
+    #[derive(Debug, Plugin)]
+    #[plugin(typed)]
+    struct Doubler;
+
+    impl TypedPlugin for Doubler {
+        type Context = i32;
+        type Output = i32;
+
+        fn run(&self, context: &i32) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+            context.checked_mul(2).ok_or_else(|| "overflow".into())
         }
     }
 
-    /// Utility method to downcast a plugin to a specific type
-    ///
-    /// It allows you to safely access the plugin's fields and methods,
-    /// not found in the `Plugin` trait.
-    pub fn with_any<P: 'static>(&self, name: &str) -> Result<&P, Box<dyn std::error::Error>> {
-        if let Some(plugin_info) = self.get_plugin(name) {
-            match plugin_info.plugin.as_any().downcast_ref::<P>() {
-                Some(plugin) => Ok(plugin),
-                None => Err(format!("Failed to downcast plugin to type P: {}", name).into()),
+    #[test]
+    fn execute_typed_test() {
+        let mut plugin_manager = PluginManager::new();
+        plugin_manager
+            .register_plugin(Box::new(Doubler), None)
+            .unwrap();
+        plugin_manager
+            .register_plugin(test_plugin("test_plugin", 1), None)
+            .unwrap();
+
+        assert_eq!(
+            plugin_manager
+                .execute_typed::<i32, i32>("doubler", &21)
+                .unwrap(),
+            42
+        );
+        assert!(plugin_manager.execute_plugin("doubler", &21).is_ok());
+
+        let result = plugin_manager.execute_typed::<&str, i32>("doubler", &"21");
+        assert!(matches!(
+            result,
+            Err(PluginError::ContextMismatch { name, expected }) if name == "doubler" && expected == "i32"
+        ));
+        let result = plugin_manager.execute_plugin("doubler", &"21");
+        assert!(matches!(result, Err(PluginError::ContextMismatch { .. })));
+
+        let result = plugin_manager.execute_typed::<i32, String>("doubler", &21);
+        assert!(matches!(result, Err(PluginError::OutputMismatch { .. })));
+
+        let result = plugin_manager.execute_typed::<i32, i32>("doubler", &i32::MAX);
+        assert!(matches!(result, Err(PluginError::Execution { .. })));
+
+        // Untyped plugins return `()`.
+        plugin_manager
+            .execute_typed::<(), ()>("test_plugin", &())
+            .unwrap();
+        let result = plugin_manager.execute_typed::<i32, i32>("missing", &1);
+        assert!(matches!(result, Err(PluginError::NotFound { .. })));
+    }
+
+    #[derive(Debug, Default, Plugin)]
+    #[plugin(lifecycle)]
+    struct PanickingPlugin {
+        panic_on_load: bool,
+    }
+
+    impl Execute for PanickingPlugin {
+        fn execute(
+            &self,
+            context: &dyn Any,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            if context.downcast_ref::<&str>() == Some(&"panic") {
+                panic!("plugin bug");
             }
-        } else {
-            Err(format!("Plugin '{}' not found", name).into())
+            Ok(())
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::path::PathBuf;
+    impl Lifecycle for PanickingPlugin {
+        fn on_load(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            if self.panic_on_load {
+                panic!("cannot load");
+            }
+            Ok(())
+        }
+    }
 
-    use super::*;
+    #[test]
+    fn panic_isolation_test() {
+        let mut plugin_manager = PluginManager::new();
+        let result = plugin_manager.register_plugin(
+            Box::new(PanickingPlugin {
+                panic_on_load: true,
+            }),
+            None,
+        );
+        assert!(matches!(
+            result,
+            Err(PluginError::PluginPanicked { message, .. }) if message == "cannot load"
+        ));
+        assert!(plugin_manager.plugins.is_empty());
 
-    fn set_env_var() {
-        let file_name = match std::env::consts::OS {
-            "linux" => "Cargo.toml",
-            "windows" => "Cargo-windows.toml",
-            "macos" => "Cargo-macos.toml",
-            _ => "Cargo.toml",
-        };
-        let file = format!("../tests/plugin_mods/{}", file_name);
-        unsafe {
-            std::env::set_var("CARGO_MANIFEST_PATH", file);
-        }
+        plugin_manager
+            .register_plugin(Box::new(PanickingPlugin::default()), None)
+            .unwrap();
+        let result = plugin_manager.execute_plugin("panicking_plugin", &"panic");
+        assert!(matches!(
+            result,
+            Err(PluginError::PluginPanicked { name, message })
+                if name == "panicking_plugin" && message == "plugin bug"
+        ));
+        // Without quarantine the plugin can still be executed.
+        assert!(
+            plugin_manager
+                .execute_plugin("panicking_plugin", &"ok")
+                .is_ok()
+        );
+
+        let mut plugin_manager = PluginManager::new().with_panic_quarantine(true);
+        plugin_manager
+            .register_plugin(Box::new(PanickingPlugin::default()), None)
+            .unwrap();
+        assert!(plugin_manager.get_quarantined_plugin_names().is_empty());
+        let result = plugin_manager.execute_plugin("panicking_plugin", &"panic");
+        assert!(matches!(result, Err(PluginError::PluginPanicked { .. })));
+        assert_eq!(
+            plugin_manager.get_quarantined_plugin_names(),
+            ["panicking_plugin"]
+        );
+        let result = plugin_manager.execute_plugin("panicking_plugin", &"ok");
+        assert!(matches!(result, Err(PluginError::Quarantined { .. })));
+
+        plugin_manager.reset_health("panicking_plugin").unwrap();
+        assert!(
+            plugin_manager
+                .execute_plugin("panicking_plugin", &"ok")
+                .is_ok()
+        );
     }
 
-    fn make_file_path(module_name: &str) -> String {
-        let mut path_name = PathBuf::new();
-        let mut module_name_prefix = String::from(std::env::consts::DLL_PREFIX);
-        module_name_prefix.push_str(module_name);
-        path_name.push("..");
-        path_name.push("target");
-        path_name.push("release");
-        path_name.push(module_name_prefix);
-        path_name.set_extension(std::env::consts::DLL_EXTENSION);
-        path_name.to_string_lossy().to_string()
+    #[test]
+    fn library_panic_isolation_test() {
+        let (_library, mut plugins) = PluginManager::new()
+            .load_plugin(&make_file_path("plugin_panics"))
+            .unwrap();
+        let mut plugin = plugins.remove(0);
+        let config: toml::Table = toml::from_str(r#"panic_in = ["configure"]"#).unwrap();
+        let result = PluginManager::configure_plugin(plugin.as_mut(), Some(&config));
+        assert!(matches!(
+            result,
+            Err(PluginError::PluginPanicked { name, message })
+                if name == "panic_a" && message == "panic_a panicked in configure"
+        ));
+
+        let config: toml::Table =
+            toml::from_str(r#"panic_in = ["activate", "deactivate"]"#).unwrap();
+        PluginManager::configure_plugin(plugin.as_mut(), Some(&config)).unwrap();
+        let mut plugin_manager = PluginManager::new().with_panic_quarantine(true);
+        plugin_manager.register_plugin(plugin, None).unwrap();
+        assert!(matches!(
+            plugin_manager.take_lifecycle_errors().as_slice(),
+            [PluginError::PluginPanicked { message, .. }] if message == "panic_a panicked in activate"
+        ));
+        assert!(plugin_manager.execute_plugin("panic_a", &"ok").is_ok());
+        let result = plugin_manager.execute_plugin("panic_a", &"panic");
+        assert!(matches!(
+            result,
+            Err(PluginError::PluginPanicked { name, message })
+                if name == "panic_a" && message == "panic_a was asked to panic"
+        ));
+        assert_eq!(plugin_manager.get_quarantined_plugin_names(), ["panic_a"]);
+
+        plugin_manager.deregister_plugin("panic_a").unwrap();
+        assert!(matches!(
+            plugin_manager.take_lifecycle_errors().as_slice(),
+            [PluginError::PluginPanicked { message, .. }] if message == "panic_a panicked in deactivate"
+        ));
     }
 
     #[test]
-    fn get_plugin_path_test() {
-        set_env_var();
-        let plugin_manager = PluginManager::new();
-        let metadata = plugin_manager.get_plugin_metadata();
-        let plugins = metadata.plugins;
-        match plugins {
-            Some(plug_entry) => {
-                for (group, entry) in plug_entry {
-                    match entry {
-                        PluginEntry::Individual(path) => {
-                            assert_eq!(path, make_file_path("plugin_mods"));
-                        }
-                        PluginEntry::Group(path) => {
-                            path.iter().for_each(|(metadata_name, path)| {
-                                assert_eq!(path, &make_file_path("plugin_inventory"));
-                                assert_eq!(metadata_name, "inventory_a");
-                                assert_eq!(group, "inventory");
-                            });
-                        }
-                    }
+    fn library_typed_plugin_test() {
+        let (_library, plugins) = PluginManager::new()
+            .load_plugin(&make_file_path("plugin_typed"))
+            .unwrap();
+        let mut plugin_manager = PluginManager::new();
+        for plugin in plugins {
+            plugin_manager.register_plugin(plugin, None).unwrap();
+        }
+        let count: usize = plugin_manager
+            .execute_typed("word_count", &"one two three".to_string())
+            .unwrap();
+        assert_eq!(count, 3);
+
+        let result = plugin_manager.execute_typed::<u32, usize>("word_count", &5);
+        assert!(matches!(
+            result,
+            Err(PluginError::ContextMismatch { name, expected })
+                if name == "word_count" && expected.ends_with("String")
+        ));
+        let result = plugin_manager.execute_plugin("word_count", &5u32);
+        assert!(matches!(result, Err(PluginError::ContextMismatch { .. })));
+    }
+
+    #[derive(Debug, Plugin)]
+    #[plugin(cancellable)]
+    struct SlowPlugin {
+        cancelled: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl Cancellable for SlowPlugin {
+        fn execute_cancellable(
+            &self,
+            context: &dyn Any,
+            token: &CancellationToken,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            let steps = *context.downcast_ref::<u32>().ok_or("expected a u32")?;
+            for _ in 0..steps {
+                if token.is_cancelled() {
+                    self.cancelled.store(true, Ordering::Relaxed);
+                    return Err("cancelled".into());
                 }
+                std::thread::sleep(Duration::from_millis(10));
             }
-            None => {
-                panic!("No plugins found in metadata");
-            }
+            Ok(())
         }
     }
 
     #[test]
-    fn activate_plugins_test() {
-        set_env_var();
+    fn execute_plugin_with_timeout_test() {
+        let cancelled = Arc::new(std::sync::atomic::AtomicBool::new(false));
         let mut plugin_manager = PluginManager::new();
-        plugin_manager = plugin_manager.activate_plugins().unwrap();
-        assert!(plugin_manager.get_plugin("plugin_a").is_some());
-        assert_eq!(plugin_manager.plugins.len(), 3);
+        plugin_manager
+            .register_plugin(
+                Box::new(SlowPlugin {
+                    cancelled: cancelled.clone(),
+                }),
+                None,
+            )
+            .unwrap();
+
+        plugin_manager
+            .execute_plugin_with_timeout("slow_plugin", 1u32, Duration::from_secs(5))
+            .unwrap();
+        let result =
+            plugin_manager.execute_plugin_with_timeout("slow_plugin", "1", Duration::from_secs(5));
+        assert!(matches!(result, Err(PluginError::Execution { .. })));
+
+        let result = plugin_manager.execute_plugin_with_timeout(
+            "slow_plugin",
+            500u32,
+            Duration::from_millis(50),
+        );
+        assert!(matches!(result, Err(PluginError::Timeout { .. })));
+        let plugin_info = plugin_manager.get_plugin("slow_plugin").unwrap();
+        assert_eq!(plugin_info.health(), PluginHealth::Unhealthy);
+
+        // The worker thread notices the cancelled token and stops.
+        for _ in 0..100 {
+            if cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(cancelled.load(Ordering::Relaxed));
+
+        plugin_manager.reset_health("slow_plugin").unwrap();
+        let plugin_info = plugin_manager.get_plugin("slow_plugin").unwrap();
+        assert_eq!(plugin_info.health(), PluginHealth::Healthy);
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = plugin_manager.execute_plugin_cancellable("slow_plugin", &1u32, &token);
+        assert!(matches!(result, Err(PluginError::Execution { .. })));
+        assert!(plugin_manager.execute_plugin("slow_plugin", &1u32).is_ok());
     }
 
-    #[test]
-    #[should_panic]
-    /// Test for duplicate activation of plugins.
-    fn activate_plugins_and_panic_test() {
-        set_env_var();
+    /// Counts its calls in the context and fails if asked to.
+    #[derive(Debug)]
+    struct BatchPlugin {
+        name: &'static str,
+        fail: bool,
+    }
+
+    impl Plugin for BatchPlugin {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn execute(
+            &self,
+            context: &dyn Any,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            let calls = context
+                .downcast_ref::<std::sync::atomic::AtomicUsize>()
+                .ok_or("expected a call counter")?;
+            calls.fetch_add(1, Ordering::Relaxed);
+            if self.fail {
+                return Err(format!("{} failed", self.name).into());
+            }
+            Ok(())
+        }
+    }
+
+    fn batch_manager() -> PluginManager {
         let mut plugin_manager = PluginManager::new();
-        plugin_manager = plugin_manager.activate_plugins().unwrap();
-        _ = plugin_manager.activate_plugins().unwrap();
+        for (name, fail) in [("first", false), ("broken", true), ("last", false)] {
+            plugin_manager
+                .register_plugin(
+                    Box::new(BatchPlugin { name, fail }),
+                    Some("batch".to_string()),
+                )
+                .unwrap();
+        }
+        plugin_manager
+            .register_plugin(test_plugin("other", 0), Some("other".to_string()))
+            .unwrap();
+        plugin_manager
     }
 
     #[test]
-    fn load_plugin_test() {
-        let plugin_manager = PluginManager::new();
-        let filename = make_file_path("plugin_mods");
-        let (_library, plugins) = plugin_manager.load_plugin(&filename).unwrap();
-        assert_eq!(plugins.len(), 2);
-        assert_eq!(plugins[0].name(), "plugin_a");
+    fn execute_group_test() {
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let plugin_manager = batch_manager();
+        let report = plugin_manager.execute_group(
+            "batch",
+            &calls,
+            ExecutionMode::Sequential,
+            FailurePolicy::CollectAll,
+        );
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+        assert!(!report.is_success());
+        assert_eq!(
+            report.results.keys().collect::<Vec<_>>(),
+            ["first", "broken", "last"]
+        );
+        let failures: Vec<_> = report.failures().map(|(name, _)| name).collect();
+        assert_eq!(failures, ["broken"]);
+        assert!(report.skipped.is_empty());
+
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let report = plugin_manager.execute_group(
+            "batch",
+            &calls,
+            ExecutionMode::Sequential,
+            FailurePolicy::FailFast,
+        );
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            report.results.keys().collect::<Vec<_>>(),
+            ["first", "broken"]
+        );
+        assert_eq!(report.skipped, ["last"]);
+
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let report = plugin_manager.execute_group(
+            "batch",
+            &calls,
+            ExecutionMode::Parallel { threads: 2 },
+            FailurePolicy::CollectAll,
+        );
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+        assert_eq!(
+            report.results.keys().collect::<Vec<_>>(),
+            ["first", "broken", "last"]
+        );
+        assert!(matches!(
+            report.results["broken"],
+            Err(PluginError::Execution { .. })
+        ));
+
+        let report = plugin_manager.execute_group(
+            "missing",
+            &calls,
+            ExecutionMode::default(),
+            FailurePolicy::default(),
+        );
+        assert!(report.results.is_empty() && report.is_success());
     }
 
     #[test]
-    fn load_plugin_and_panic_test() {
-        let plugin_manager = PluginManager::new();
-        let filename = make_file_path("plugin_mods");
-        let (_library, _) = plugin_manager.load_plugin(&filename).unwrap();
-        let filename = make_file_path("plugin_mods");
-        let (_library, plugins) = plugin_manager.load_plugin(&filename).unwrap();
-        assert_eq!(plugins.len(), 2);
-        assert_eq!(plugins[0].name(), "plugin_a");
+    fn pipeline_test() {
+        let mut plugin_manager = PluginManager::new();
+        plugin_manager
+            .register_plugin(Box::new(Doubler), None)
+            .unwrap();
+        plugin_manager
+            .register_plugin(test_plugin("test_plugin", 1), None)
+            .unwrap();
+
+        let pipeline = plugin_manager
+            .pipeline("doubler")
+            .then("doubler")
+            .then("doubler");
+        assert_eq!(pipeline.stages(), ["doubler", "doubler", "doubler"]);
+        assert_eq!(pipeline.run::<i32, i32>(5).unwrap(), 40);
+
+        let error = pipeline.run::<i32, i32>(1 << 29).unwrap_err();
+        assert_eq!(error.stage, 1);
+        assert_eq!(error.name, "doubler");
+        assert_eq!(error.input.downcast_ref::<i32>(), Some(&(1 << 30)));
+        assert!(matches!(*error.source, PluginError::Execution { .. }));
+
+        let error = pipeline.run::<&str, i32>("5").unwrap_err();
+        assert_eq!(error.stage, 0);
+        assert!(matches!(*error.source, PluginError::ContextMismatch { .. }));
+
+        let error = plugin_manager
+            .pipeline("doubler")
+            .then("test_plugin")
+            .run::<i32, i32>(5)
+            .unwrap_err();
+        assert_eq!(error.stage, 1);
+        assert_eq!(error.input.downcast_ref::<i32>(), Some(&10));
+        assert!(matches!(*error.source, PluginError::OutputMismatch { .. }));
+
+        let error = plugin_manager
+            .pipeline("doubler")
+            .then("missing")
+            .run::<i32, i32>(5)
+            .unwrap_err();
+        assert_eq!(error.name, "missing");
+        assert!(matches!(*error.source, PluginError::NotFound { .. }));
     }
 
-    #[test]
-    fn activate_plugins_with_groups_test() {
-        set_env_var();
-        let plugin_manager = PluginManager::new().activate_plugins().unwrap();
+    #[derive(Debug, Plugin)]
+    #[plugin(hooks)]
+    struct Auditor;
 
-        // Check individual plugin
-        let plugin_a = plugin_manager.get_plugin("plugin_a").unwrap();
-        assert_eq!(plugin_a.group, None);
+    impl Execute for Auditor {
+        fn execute(
+            &self,
+            _context: &dyn Any,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
+    }
 
-        // Check grouped plugin
-        let inventory_plugin = plugin_manager.get_plugin("inventory_a").unwrap();
-        assert_eq!(inventory_plugin.group, Some("inventory".to_string()));
+    impl Hooks for Auditor {
+        fn hooks(&self) -> Vec<HookSubscription> {
+            vec![
+                HookSubscription::new("before_save", 0),
+                HookSubscription::new("on_request", 5),
+            ]
+        }
+    }
 
-        // Get all plugins in the "inventory" group
-        let inventory_plugins = plugin_manager.get_plugins_by_group("inventory");
-        assert_eq!(inventory_plugins.len(), 1);
-        assert_eq!(inventory_plugins[0].plugin.name(), "inventory_a");
+    #[derive(Debug, Plugin)]
+    #[plugin(hooks)]
+    struct Validator;
 
-        assert_eq!(plugin_manager.plugins.len(), 3);
+    impl Execute for Validator {
+        fn execute(
+            &self,
+            _context: &dyn Any,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
+        }
     }
 
-    #[test]
-    fn get_all_plugin_names_and_groups_test() {
-        set_env_var();
-        let plugin_manager = PluginManager::new().activate_plugins().unwrap();
-        let all_plugins = plugin_manager.get_all_plugin_names_and_groups();
-        assert_eq!(all_plugins.len(), 3);
-        all_plugins
-            .iter()
-            .for_each(|(name, group)| match name.as_str() {
-                "plugin_a" => assert_eq!(group.as_deref(), None),
-                "plugin_b" => assert_eq!(group.as_deref(), None),
-                "inventory_a" => assert_eq!(group.as_deref(), Some("inventory")),
-                _ => panic!("Unexpected plugin name"),
-            });
+    impl Hooks for Validator {
+        fn hooks(&self) -> Vec<HookSubscription> {
+            vec![HookSubscription::new("before_save", 10)]
+        }
+
+        fn handle_hook(
+            &self,
+            point: &str,
+            context: &dyn Any,
+        ) -> Result<Box<dyn Any + Send>, Box<dyn std::error::Error + Send + Sync>> {
+            let document = context
+                .downcast_ref::<&str>()
+                .ok_or("expected a document")?;
+            if document.is_empty() {
+                return Err("the document is empty".into());
+            }
+            Ok(Box::new(format!("{point}: {document}")))
+        }
     }
 
     #[test]
-    fn deregister_plugin_test() {
-        set_env_var();
-        let mut plugin_manager = PluginManager::new().activate_plugins().unwrap();
-        assert_eq!(plugin_manager.plugins.len(), 3);
-
-        // Deregister individual plugin
-        let plugin_name = plugin_manager.deregister_plugin("plugin_a");
-        if let Some(plugin) = plugin_name {
-            assert_eq!(plugin, "plugin_a");
-            assert_eq!(plugin_manager.plugins.len(), 2);
+    fn invoke_hook_test() {
+        let mut plugin_manager = PluginManager::new()
+            .with_hook("before_save")
+            .with_hook("on_request");
+        for plugin in [
+            Box::new(Auditor) as Box<dyn Plugin>,
+            test_plugin("test_plugin", 1),
+            Box::new(Validator),
+        ] {
+            plugin_manager.register_plugin(plugin, None).unwrap();
         }
 
-        // Deregister grouped plugin
-        let plugin_name = plugin_manager.deregister_plugin("inventory_a");
-        if let Some(plugin) = plugin_name {
-            assert_eq!(plugin, "inventory_a");
-            assert_eq!(plugin_manager.plugins.len(), 1);
+        assert_eq!(
+            plugin_manager.get_hook_subscribers("before_save"),
+            ["validator", "auditor"]
+        );
+        assert_eq!(
+            plugin_manager.get_hook_subscribers("on_request"),
+            ["auditor"]
+        );
+
+        let report = plugin_manager.invoke_hook("before_save", &"doc").unwrap();
+        assert!(report.is_success());
+        assert_eq!(
+            report.results.keys().collect::<Vec<_>>(),
+            ["validator", "auditor"]
+        );
+        let outputs: Vec<_> = report.outputs::<String>().collect();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].1, "before_save: doc");
+
+        let report = plugin_manager.invoke_hook("before_save", &"").unwrap();
+        assert!(!report.is_success());
+        let failures: Vec<_> = report.failures().map(|(name, _)| name).collect();
+        assert_eq!(failures, ["validator"]);
+        assert!(report.results["auditor"].is_ok());
+
+        let report = plugin_manager.invoke_hook("on_request", &()).unwrap();
+        assert_eq!(report.results.len(), 1);
+
+        let result = plugin_manager.invoke_hook("after_save", &());
+        assert!(matches!(
+            result,
+            Err(PluginError::UnknownHook { point }) if point == "after_save"
+        ));
+    }
+
+    #[derive(Debug, Plugin)]
+    #[plugin(hooks)]
+    struct BrokenSubscriber;
+
+    impl Execute for BrokenSubscriber {
+        fn execute(
+            &self,
+            _context: &dyn Any,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Ok(())
         }
+    }
 
-        // Deregister non-existent plugin
-        let plugin_name = plugin_manager.deregister_plugin("non_existent_plugin");
-        assert_eq!(plugin_name, None);
+    impl Hooks for BrokenSubscriber {
+        fn hooks(&self) -> Vec<HookSubscription> {
+            panic!("no subscriptions")
+        }
     }
 
     #[test]
-    fn deregister_all_plugins_test() {
-        set_env_var();
-        let mut plugin_manager = PluginManager::new().activate_plugins().unwrap();
-        assert_eq!(plugin_manager.plugins.len(), 3);
+    fn hooks_panic_test() {
+        assert!(Plugin::hooks(&BrokenSubscriber).is_empty());
 
-        // Deregister all plugins
-        let num_plugins_deregistered = plugin_manager.deregister_all_plugins();
-        assert_eq!(num_plugins_deregistered.len(), 3);
-        assert_eq!(plugin_manager.plugins.len(), 0);
+        let mut plugin_manager = PluginManager::new();
+        let result = plugin_manager.register_plugin(Box::new(BrokenSubscriber), None);
+        assert!(matches!(
+            result,
+            Err(PluginError::PluginPanicked { name, message })
+                if name == "broken_subscriber" && message == "no subscriptions"
+        ));
+        assert!(plugin_manager.plugins.is_empty());
+    }
+
+    #[derive(Debug, Plugin)]
+    struct IoPlugin;
+
+    impl Execute for IoPlugin {
+        fn execute(
+            &self,
+            _context: &dyn Any,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Err(std::io::Error::new(std::io::ErrorKind::NotFound, "inventory.csv").into())
+        }
+    }
+
+    #[test]
+    fn execution_error_source_test() {
+        let mut plugin_manager = PluginManager::new();
+        plugin_manager
+            .register_plugin(Box::new(IoPlugin), None)
+            .unwrap();
+        let error = plugin_manager.execute_plugin("io_plugin", &()).unwrap_err();
+        assert!(matches!(error, PluginError::Execution { .. }));
+        let source = std::error::Error::source(&error).unwrap();
+        let io_error = source.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io_error.kind(), std::io::ErrorKind::NotFound);
     }
 
     // TODO: write a test for PluginManager::execute_plugin
@@ -0,0 +1,90 @@
+//! Isolation of panics raised by plugin code.
+
+use crate::PluginError;
+use std::any::Any;
+use std::error::Error;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+/// Calls into plugin code, turning a panic into [`PluginError::PluginPanicked`]
+/// instead of unwinding into the host.
+///
+/// `name` identifies the plugin, or the library when no plugin exists yet.
+pub(crate) fn catch_panic<T>(name: &str, call: impl FnOnce() -> T) -> Result<T, PluginError> {
+    catch_unwind(AssertUnwindSafe(call)).map_err(|payload| {
+        let error = PluginError::PluginPanicked {
+            name: name.to_string(),
+            message: panic_message(payload.as_ref()),
+        };
+        log::error!("{error}");
+        error
+    })
+}
+
+/// Calls plugin code from within its own library, returning the panic
+/// message instead of unwinding.
+///
+/// A panic cannot unwind out of a plugin library into the host, so the
+/// [`declare_plugins!`](crate::declare_plugins) macro and `#[derive(Plugin)]`
+/// catch it on the plugin's side of the boundary.
+pub fn catch_message<T>(call: impl FnOnce() -> T) -> Result<T, String> {
+    catch_unwind(AssertUnwindSafe(call)).map_err(|payload| panic_message(payload.as_ref()))
+}
+
+/// Calls a fallible plugin method from within its own library, turning a
+/// panic into a [`PluginError::PluginPanicked`] error.
+pub fn catch_plugin_panic<T>(
+    name: &str,
+    call: impl FnOnce() -> Result<T, Box<dyn Error + Send + Sync>>,
+) -> Result<T, Box<dyn Error + Send + Sync>> {
+    catch_message(call).unwrap_or_else(|message| {
+        Err(Box::new(PluginError::PluginPanicked {
+            name: name.to_string(),
+            message,
+        }))
+    })
+}
+
+/// The message given to `panic!`, when it is a string.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catch_panic_test() {
+        assert_eq!(catch_panic("plugin_a", || 1).unwrap(), 1);
+
+        let result = catch_panic("plugin_a", || panic!("boom"));
+        assert!(matches!(
+            result,
+            Err(PluginError::PluginPanicked { name, message }) if name == "plugin_a" && message == "boom"
+        ));
+
+        let count = 3;
+        let result = catch_panic("plugin_a", || panic!("failed after {count} retries"));
+        assert!(matches!(
+            result,
+            Err(PluginError::PluginPanicked { message, .. }) if message == "failed after 3 retries"
+        ));
+    }
+
+    #[test]
+    fn catch_plugin_panic_test() {
+        assert_eq!(catch_plugin_panic("plugin_a", || Ok(1)).unwrap(), 1);
+
+        let error = catch_plugin_panic::<()>("plugin_a", || panic!("boom")).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<PluginError>(),
+            Some(PluginError::PluginPanicked { name, message }) if name == "plugin_a" && message == "boom"
+        ));
+    }
+}
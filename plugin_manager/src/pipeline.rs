@@ -0,0 +1,149 @@
+//! Chains of plugins that pass each output on as the next context.
+
+use crate::{PluginError, PluginManager};
+use std::any::{Any, type_name};
+use std::error::Error;
+use std::fmt;
+
+/// An ordered list of plugins, each executed with the output of the one
+/// before it.
+///
+/// Built with [`PluginManager::pipeline`]. Every stage is executed through
+/// [`Plugin::execute_any`](crate::Plugin::execute_any), so a plain plugin
+/// passes `()` on to the next stage while a [`TypedPlugin`](crate::TypedPlugin)
+/// passes its output:
+///
+/// ```rust
+/// use plugin_manager::{Plugin, PluginManager, TypedPlugin};
+///
+/// #[derive(Debug, Plugin)]
+/// #[plugin(typed)]
+/// struct Parse;
+///
+/// impl TypedPlugin for Parse {
+///     type Context = String;
+///     type Output = Vec<i32>;
+///
+///     fn run(&self, context: &String) -> Result<Vec<i32>, Box<dyn std::error::Error + Send + Sync>> {
+///         Ok(context.split(',').map(str::parse).collect::<Result<_, _>>()?)
+///     }
+/// }
+///
+/// #[derive(Debug, Plugin)]
+/// #[plugin(typed)]
+/// struct Sum;
+///
+/// impl TypedPlugin for Sum {
+///     type Context = Vec<i32>;
+///     type Output = i32;
+///
+///     fn run(&self, context: &Vec<i32>) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+///         Ok(context.iter().sum())
+///     }
+/// }
+///
+/// let mut plugin_manager = PluginManager::new();
+/// plugin_manager.register_plugin(Box::new(Parse), None).unwrap();
+/// plugin_manager.register_plugin(Box::new(Sum), None).unwrap();
+///
+/// let pipeline = plugin_manager.pipeline("parse").then("sum");
+/// let total: i32 = pipeline.run("1,2,3".to_string()).unwrap();
+/// assert_eq!(total, 6);
+///
+/// let error = pipeline.run::<_, i32>("1,x".to_string()).unwrap_err();
+/// assert_eq!(error.name, "parse");
+/// assert_eq!(error.input.downcast_ref::<String>().unwrap(), "1,x");
+/// ```
+#[derive(Clone)]
+pub struct Pipeline<'a> {
+    plugin_manager: &'a PluginManager,
+    stages: Vec<String>,
+}
+
+impl<'a> Pipeline<'a> {
+    pub(crate) fn new(plugin_manager: &'a PluginManager, first: &str) -> Self {
+        Pipeline {
+            plugin_manager,
+            stages: vec![first.to_string()],
+        }
+    }
+
+    /// Adds a stage that is executed with the output of the previous one.
+    pub fn then(mut self, name: &str) -> Self {
+        self.stages.push(name.to_string());
+        self
+    }
+
+    /// The names of the plugins in the pipeline, in the order they run.
+    pub fn stages(&self) -> &[String] {
+        &self.stages
+    }
+
+    /// Runs the stages in order, starting with `input`, and returns the
+    /// output of the last stage.
+    ///
+    /// Stops at the first stage that fails, returning a [`PipelineError`]
+    /// with the input it was given. The last stage also fails with
+    /// [`PluginError::OutputMismatch`] if its output is not an `O`.
+    pub fn run<I: Any + Send, O: Any>(&self, input: I) -> Result<O, PipelineError> {
+        let mut input: Box<dyn Any + Send> = Box::new(input);
+        let last = self.stages.len() - 1;
+        for (stage, name) in self.stages.iter().enumerate() {
+            let fail = |input, source| {
+                let error = PipelineError {
+                    stage,
+                    name: name.clone(),
+                    input,
+                    source: Box::new(source),
+                };
+                log::error!("{error}");
+                error
+            };
+            let output = match self
+                .plugin_manager
+                .invoke(name, |plugin| plugin.execute_any(input.as_ref()))
+            {
+                Ok(output) => output,
+                Err(source) => return Err(fail(input, source)),
+            };
+            if stage == last && !output.is::<O>() {
+                let source = PluginError::OutputMismatch {
+                    name: name.clone(),
+                    expected: type_name::<O>(),
+                };
+                return Err(fail(input, source));
+            }
+            input = output;
+        }
+        Ok(*input.downcast::<O>().expect("the output type was checked"))
+    }
+}
+
+/// A stage of a [`Pipeline`] that failed.
+#[derive(Debug)]
+pub struct PipelineError {
+    /// The position of the stage in the pipeline, starting at 0.
+    pub stage: usize,
+    /// The name of the plugin executed by the stage.
+    pub name: String,
+    /// The value the stage was executed with.
+    pub input: Box<dyn Any + Send>,
+    /// Why the stage failed.
+    pub source: Box<PluginError>,
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Pipeline stage {} ('{}') failed: {}",
+            self.stage, self.name, self.source
+        )
+    }
+}
+
+impl Error for PipelineError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
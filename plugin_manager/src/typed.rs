@@ -0,0 +1,65 @@
+//! Plugins with a typed context and a typed output.
+
+use crate::{Plugin, PluginError};
+use std::any::{Any, type_name};
+use std::error::Error;
+
+/// A plugin that takes a context of a known type and returns an output.
+///
+/// [`Plugin::execute`] only passes a `&dyn Any` and returns `()`. A typed
+/// plugin implements [`run`](TypedPlugin::run) instead, and its [`Plugin`]
+/// implementation forwards to [`run_any`](TypedPlugin::run_any), which checks
+/// the type of the context. `#[derive(Plugin)]` does so with `#[plugin(typed)]`:
+///
+/// ```rust
+/// use plugin_manager::{Plugin, PluginManager, TypedPlugin};
+///
+/// #[derive(Debug, Plugin)]
+/// #[plugin(name = "word_count", typed)]
+/// struct WordCount;
+///
+/// impl TypedPlugin for WordCount {
+///     type Context = String;
+///     type Output = usize;
+///
+///     fn run(&self, context: &String) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+///         Ok(context.split_whitespace().count())
+///     }
+/// }
+///
+/// let mut plugin_manager = PluginManager::new();
+/// plugin_manager.register_plugin(Box::new(WordCount), None).unwrap();
+/// let count: usize = plugin_manager
+///     .execute_typed("word_count", &"one two three".to_string())
+///     .unwrap();
+/// assert_eq!(count, 3);
+/// ```
+pub trait TypedPlugin {
+    /// The type of the context the plugin is executed with.
+    type Context: Any;
+    /// The type of the value the plugin returns.
+    type Output: Any + Send;
+
+    /// Executes the plugin with its context.
+    fn run(&self, context: &Self::Context) -> Result<Self::Output, Box<dyn Error + Send + Sync>>;
+
+    /// Runs the plugin with a type-erased context, for [`Plugin::execute_any`].
+    ///
+    /// Fails with [`PluginError::ContextMismatch`] if the context is not a
+    /// [`Context`](TypedPlugin::Context).
+    fn run_any(
+        &self,
+        context: &dyn Any,
+    ) -> Result<Box<dyn Any + Send>, Box<dyn Error + Send + Sync>>
+    where
+        Self: Plugin + Sized,
+    {
+        let context = context.downcast_ref::<Self::Context>().ok_or_else(|| {
+            PluginError::ContextMismatch {
+                name: self.name(),
+                expected: type_name::<Self::Context>().to_string(),
+            }
+        })?;
+        Ok(Box::new(self.run(context)?))
+    }
+}
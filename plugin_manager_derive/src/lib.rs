@@ -0,0 +1,376 @@
+//! # Plugin Manager Derive
+//!
+//! Provides `#[derive(Plugin)]` for the `plugin_manager` crate. The derive
+//! implements the boilerplate methods of the `Plugin` trait and forwards
+//! `execute` to the `Execute` trait, which is the only part a plugin author
+//! writes by hand.
+//!
+//! The `#[plugin(...)]` attribute accepts:
+//!
+//! - `name`: the name the plugin is registered under. Defaults to the type
+//!   name in snake case.
+//! - `group`: the group the plugin belongs to when its manifest entry does
+//!   not set one.
+//! - `version`: the semver version reported by the plugin, checked at compile time.
+//! - `description`: a short description of the plugin.
+//! - `authors(...)`, `tags(...)` and `capabilities(...)`: lists of strings
+//!   describing the plugin, such as `capabilities("export-csv")`.
+//! - `depends_on("a", "b")`: the plugins that must be registered first.
+//! - `configure`: forwards `configure` to the `Configure` trait, so the
+//!   plugin receives its settings from the manifest.
+//! - `typed`: forwards `execute` and `execute_any` to the `TypedPlugin` trait
+//!   instead of `execute` to the `Execute` trait.
+//! - `cancellable`: forwards `execute` and `execute_cancellable` to the
+//!   `Cancellable` trait, so the plugin can check its cancellation token.
+//! - `lifecycle`: forwards the lifecycle hooks (`on_load`, `on_activate`,
+//!   `on_deactivate` and `on_unload`) to the `Lifecycle` trait.
+//! - `hooks`: forwards `hooks` and `handle_hook` to the `Hooks` trait, so the
+//!   plugin subscribes to the host's extension points.
+//!
+//! The forwarded methods catch panics within the plugin's own library and
+//! return them as `PluginError::PluginPanicked`, since a panic cannot unwind
+//! from a plugin library into the host.
+//!
+//! ```rust,ignore
+//! use plugin_manager::{Execute, Plugin};
+//! use std::any::Any;
+//!
+//! #[derive(Debug, Plugin)]
+//! #[plugin(name = "plugin_a", group = "inventory", version = "1.2.0")]
+//! struct PluginA;
+//!
+//! impl Execute for PluginA {
+//!     fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!         Ok(())
+//!     }
+//! }
+//! ```
+
+use heck::ToSnakeCase;
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{DeriveInput, Ident, LitStr, Token, parse_macro_input};
+
+#[proc_macro_derive(Plugin, attributes(plugin))]
+pub fn derive_plugin(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// The values given in `#[plugin(...)]` attributes.
+#[derive(Default)]
+struct PluginAttributes {
+    name: Option<LitStr>,
+    group: Option<LitStr>,
+    version: Option<LitStr>,
+    description: Option<LitStr>,
+    depends_on: Option<Vec<LitStr>>,
+    authors: Option<Vec<LitStr>>,
+    tags: Option<Vec<LitStr>>,
+    capabilities: Option<Vec<LitStr>>,
+    configure: bool,
+    lifecycle: bool,
+    typed: bool,
+    cancellable: bool,
+    hooks: bool,
+}
+
+impl PluginAttributes {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut attributes = PluginAttributes::default();
+        for attr in input.attrs.iter().filter(|a| a.path().is_ident("plugin")) {
+            attr.parse_nested_meta(|meta| {
+                let duplicate = || meta.error("duplicate plugin attribute");
+                let flag = if meta.path.is_ident("configure") {
+                    Some(&mut attributes.configure)
+                } else if meta.path.is_ident("lifecycle") {
+                    Some(&mut attributes.lifecycle)
+                } else if meta.path.is_ident("typed") {
+                    Some(&mut attributes.typed)
+                } else if meta.path.is_ident("cancellable") {
+                    Some(&mut attributes.cancellable)
+                } else if meta.path.is_ident("hooks") {
+                    Some(&mut attributes.hooks)
+                } else {
+                    None
+                };
+                if let Some(flag) = flag {
+                    if *flag {
+                        return Err(duplicate());
+                    }
+                    *flag = true;
+                    return Ok(());
+                }
+                let list = if meta.path.is_ident("depends_on") {
+                    Some(&mut attributes.depends_on)
+                } else if meta.path.is_ident("authors") {
+                    Some(&mut attributes.authors)
+                } else if meta.path.is_ident("tags") {
+                    Some(&mut attributes.tags)
+                } else if meta.path.is_ident("capabilities") {
+                    Some(&mut attributes.capabilities)
+                } else {
+                    None
+                };
+                if let Some(list) = list {
+                    if list.is_some() {
+                        return Err(duplicate());
+                    }
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    let values = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+                    *list = Some(values.into_iter().collect());
+                    return Ok(());
+                }
+                let slot = if meta.path.is_ident("name") {
+                    &mut attributes.name
+                } else if meta.path.is_ident("group") {
+                    &mut attributes.group
+                } else if meta.path.is_ident("version") {
+                    &mut attributes.version
+                } else if meta.path.is_ident("description") {
+                    &mut attributes.description
+                } else {
+                    return Err(meta.error("unsupported plugin attribute"));
+                };
+                if slot.is_some() {
+                    return Err(duplicate());
+                }
+                *slot = Some(meta.value()?.parse()?);
+                Ok(())
+            })?;
+        }
+        Ok(attributes)
+    }
+}
+
+/// Implements a `Plugin` method returning `Some` of the given string.
+fn string_method(method: &str, value: Option<LitStr>) -> Option<TokenStream2> {
+    let method = Ident::new(method, Span::call_site());
+    value.map(|value| {
+        quote! {
+            fn #method(&self) -> ::std::option::Option<::std::string::String> {
+                ::std::option::Option::Some(::std::string::String::from(#value))
+            }
+        }
+    })
+}
+
+/// Implements a `Plugin` method returning the given list of strings.
+fn list_method(method: &str, values: Option<Vec<LitStr>>) -> Option<TokenStream2> {
+    let method = Ident::new(method, Span::call_site());
+    values.map(|values| {
+        quote! {
+            fn #method(&self) -> ::std::vec::Vec<::std::string::String> {
+                ::std::vec![#(::std::string::String::from(#values)),*]
+            }
+        }
+    })
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let attributes = PluginAttributes::parse(input)?;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let name = attributes
+        .name
+        .unwrap_or_else(|| LitStr::new(&ident.to_string().to_snake_case(), ident.span()));
+    if let Some(version) = &attributes.version {
+        semver::Version::parse(&version.value()).map_err(|error| {
+            syn::Error::new(version.span(), format!("invalid plugin version: {error}"))
+        })?;
+    }
+    let group = string_method("group", attributes.group);
+    let version = string_method("version", attributes.version);
+    let description = string_method("description", attributes.description);
+    let depends_on = list_method("depends_on", attributes.depends_on);
+    let authors = list_method("authors", attributes.authors);
+    let tags = list_method("tags", attributes.tags);
+    let capabilities = list_method("capabilities", attributes.capabilities);
+    if attributes.typed && attributes.cancellable {
+        return Err(syn::Error::new(
+            ident.span(),
+            "`typed` and `cancellable` cannot be combined",
+        ));
+    }
+    let execute = if attributes.cancellable {
+        quote! {
+            fn execute(
+                &self,
+                context: &dyn ::std::any::Any,
+            ) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>> {
+                ::plugin_manager::__private::catch_plugin_panic(#name, || {
+                    ::plugin_manager::Cancellable::execute_cancellable(
+                        self,
+                        context,
+                        &::plugin_manager::CancellationToken::new(),
+                    )
+                })
+            }
+
+            fn execute_cancellable(
+                &self,
+                context: &dyn ::std::any::Any,
+                token: &::plugin_manager::CancellationToken,
+            ) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>> {
+                ::plugin_manager::__private::catch_plugin_panic(#name, || {
+                    ::plugin_manager::Cancellable::execute_cancellable(self, context, token)
+                })
+            }
+        }
+    } else if attributes.typed {
+        quote! {
+            fn execute(
+                &self,
+                context: &dyn ::std::any::Any,
+            ) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>> {
+                ::plugin_manager::__private::catch_plugin_panic(#name, || {
+                    ::plugin_manager::TypedPlugin::run_any(self, context).map(|_| ())
+                })
+            }
+
+            fn execute_any(
+                &self,
+                context: &dyn ::std::any::Any,
+            ) -> ::std::result::Result<
+                ::std::boxed::Box<dyn ::std::any::Any + ::std::marker::Send>,
+                ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>,
+            > {
+                ::plugin_manager::__private::catch_plugin_panic(#name, || {
+                    ::plugin_manager::TypedPlugin::run_any(self, context)
+                })
+            }
+        }
+    } else {
+        quote! {
+            fn execute(
+                &self,
+                context: &dyn ::std::any::Any,
+            ) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>> {
+                ::plugin_manager::__private::catch_plugin_panic(#name, || {
+                    ::plugin_manager::Execute::execute(self, context)
+                })
+            }
+        }
+    };
+    let configure = attributes.configure.then(|| {
+        quote! {
+            fn configure(
+                &mut self,
+                config: &::plugin_manager::toml::Value,
+            ) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>> {
+                ::plugin_manager::__private::catch_plugin_panic(#name, || {
+                    ::plugin_manager::Configure::configure(self, config)
+                })
+            }
+        }
+    });
+    let lifecycle = attributes.lifecycle.then(|| {
+        quote! {
+            fn on_load(
+                &mut self,
+            ) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>> {
+                ::plugin_manager::__private::catch_plugin_panic(#name, || {
+                    ::plugin_manager::Lifecycle::on_load(self)
+                })
+            }
+
+            fn on_activate(&self) {
+                // The manager calls `try_on_activate`, which returns the panic.
+                let _ = ::plugin_manager::Plugin::try_on_activate(self);
+            }
+
+            fn on_deactivate(&self) {
+                // The manager calls `try_on_deactivate`, which returns the panic.
+                let _ = ::plugin_manager::Plugin::try_on_deactivate(self);
+            }
+
+            fn try_on_activate(
+                &self,
+            ) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>> {
+                ::plugin_manager::__private::catch_plugin_panic(#name, || {
+                    ::plugin_manager::Lifecycle::on_activate(self);
+                    ::std::result::Result::Ok(())
+                })
+            }
+
+            fn try_on_deactivate(
+                &self,
+            ) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>> {
+                ::plugin_manager::__private::catch_plugin_panic(#name, || {
+                    ::plugin_manager::Lifecycle::on_deactivate(self);
+                    ::std::result::Result::Ok(())
+                })
+            }
+
+            fn on_unload(
+                &mut self,
+            ) -> ::std::result::Result<(), ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>> {
+                ::plugin_manager::__private::catch_plugin_panic(#name, || {
+                    ::plugin_manager::Lifecycle::on_unload(self)
+                })
+            }
+        }
+    });
+    let hooks = attributes.hooks.then(|| {
+        quote! {
+            fn hooks(&self) -> ::std::vec::Vec<::plugin_manager::HookSubscription> {
+                // The manager calls `try_hooks`, which returns the panic.
+                ::plugin_manager::Plugin::try_hooks(self).unwrap_or_default()
+            }
+
+            fn try_hooks(
+                &self,
+            ) -> ::std::result::Result<
+                ::std::vec::Vec<::plugin_manager::HookSubscription>,
+                ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>,
+            > {
+                ::plugin_manager::__private::catch_plugin_panic(#name, || {
+                    ::std::result::Result::Ok(::plugin_manager::Hooks::hooks(self))
+                })
+            }
+
+            fn handle_hook(
+                &self,
+                point: &str,
+                context: &dyn ::std::any::Any,
+            ) -> ::std::result::Result<
+                ::std::boxed::Box<dyn ::std::any::Any + ::std::marker::Send>,
+                ::std::boxed::Box<dyn ::std::error::Error + ::std::marker::Send + ::std::marker::Sync>,
+            > {
+                ::plugin_manager::__private::catch_plugin_panic(#name, || {
+                    ::plugin_manager::Hooks::handle_hook(self, point, context)
+                })
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::plugin_manager::Plugin for #ident #ty_generics #where_clause {
+            fn as_any(&self) -> &dyn ::std::any::Any {
+                self
+            }
+
+            fn name(&self) -> ::std::string::String {
+                ::std::string::String::from(#name)
+            }
+
+            #execute
+            #group
+            #version
+            #description
+            #authors
+            #tags
+            #capabilities
+            #depends_on
+            #configure
+            #lifecycle
+            #hooks
+        }
+    })
+}
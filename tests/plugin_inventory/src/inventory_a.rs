@@ -1,22 +1,21 @@
-use plugin_manager::Plugin;
+use plugin_manager::{Execute, Plugin};
 use std::any::Any;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Plugin)]
+#[plugin(
+    name = "inventory_a",
+    version = "0.1.0",
+    description = "Collects the inventory",
+    tags("inventory"),
+    capabilities("export-csv")
+)]
 pub struct InventoryA;
 
-impl Plugin for InventoryA {
-    fn name(&self) -> String {
-        String::from("inventory_a")
-    }
-
-    fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error>> {
+impl Execute for InventoryA {
+    fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         println!("Executing Inventory A");
         Ok(())
     }
-
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
 }
 impl InventoryA {
     pub fn other_method(&self) {
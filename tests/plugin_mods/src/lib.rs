@@ -1,10 +1,4 @@
 pub mod plugin_a;
 pub mod plugin_b;
-use plugin_manager::Plugin;
 
-#[unsafe(no_mangle)]
-pub fn create_plugins() -> Vec<Box<dyn Plugin>> {
-    let plugins: Vec<Box<dyn Plugin>> =
-        vec![Box::new(plugin_a::PluginA), Box::new(plugin_b::PluginB)];
-    plugins
-}
+plugin_manager::declare_plugins!(plugin_a::PluginA, plugin_b::PluginB);
@@ -1,30 +1,18 @@
-use plugin_manager::Plugin;
+use plugin_manager::{Execute, Plugin};
 use std::any::Any;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Plugin)]
+#[plugin(name = "plugin_a", version = "0.1.0", tags("mods"))]
 pub struct PluginA;
 
-impl Plugin for PluginA {
-    fn name(&self) -> String {
-        String::from("plugin_a")
-    }
-
-    fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error>> {
+impl Execute for PluginA {
+    fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         println!("Executing Plugin A");
         Ok(())
     }
-
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
 }
 impl PluginA {
     pub fn other_method(&self) {
         println!("Executing other method in Plugin A");
     }
 }
-
-#[unsafe(no_mangle)]
-pub fn create_plugin() -> Box<dyn Plugin> {
-    Box::new(PluginA)
-}
@@ -1,30 +1,18 @@
-use plugin_manager::Plugin;
+use plugin_manager::{Execute, Plugin};
 use std::any::Any;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Plugin)]
+#[plugin(name = "plugin_b", version = "0.1.0", tags("mods"))]
 pub struct PluginB;
 
-impl Plugin for PluginB {
-    fn name(&self) -> String {
-        String::from("plugin_b")
-    }
-
-    fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error>> {
+impl Execute for PluginB {
+    fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         println!("Executing Plugin B");
         Ok(())
     }
-
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
 }
 impl PluginB {
     pub fn other_method(&self) {
         println!("Executing other method in Plugin B");
     }
 }
-
-// #[unsafe(no_mangle)]
-// pub fn create_plugin() -> Box<dyn Plugin> {
-//     Box::new(PluginB)
-// }
@@ -0,0 +1,3 @@
+pub mod panic_a;
+
+plugin_manager::declare_plugins!(panic_a::PanicA::default());
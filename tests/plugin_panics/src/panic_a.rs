@@ -0,0 +1,55 @@
+use plugin_manager::{Configure, Execute, Lifecycle, Plugin, toml};
+use std::any::Any;
+
+/// Panics when executed with the context `"panic"`, or in the methods listed
+/// in the `panic_in` setting, such as `panic_in = ["configure"]`.
+#[derive(Debug, Default, Plugin)]
+#[plugin(name = "panic_a", version = "0.1.0", configure, lifecycle)]
+pub struct PanicA {
+    panic_in: Vec<String>,
+}
+
+impl PanicA {
+    fn panic_in(&self, method: &str) {
+        if self.panic_in.iter().any(|m| m == method) {
+            panic!("panic_a panicked in {method}");
+        }
+    }
+}
+
+impl Execute for PanicA {
+    fn execute(&self, context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if context.downcast_ref::<&str>() == Some(&"panic") {
+            panic!("panic_a was asked to panic");
+        }
+        Ok(())
+    }
+}
+
+impl Configure for PanicA {
+    fn configure(
+        &mut self,
+        config: &toml::Value,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.panic_in = config
+            .get("panic_in")
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(toml::Value::as_str)
+            .map(str::to_string)
+            .collect();
+        self.panic_in("configure");
+        Ok(())
+    }
+}
+
+impl Lifecycle for PanicA {
+    fn on_activate(&self) {
+        self.panic_in("activate");
+    }
+
+    fn on_deactivate(&self) {
+        self.panic_in("deactivate");
+    }
+}
@@ -1,8 +1,3 @@
 pub mod task_a;
-use plugin_manager::Plugin;
 
-#[unsafe(no_mangle)]
-pub fn create_plugins() -> Vec<Box<dyn Plugin>> {
-    let plugins: Vec<Box<dyn Plugin>> = vec![Box::new(task_a::TaskA)];
-    plugins
-}
+plugin_manager::declare_plugins!(task_a::TaskA);
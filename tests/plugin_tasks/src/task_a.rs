@@ -1,22 +1,15 @@
-use plugin_manager::Plugin;
+use plugin_manager::{Execute, Plugin};
 use std::any::Any;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Plugin)]
+#[plugin(name = "task_a", version = "0.1.0")]
 pub struct TaskA;
 
-impl Plugin for TaskA {
-    fn name(&self) -> String {
-        String::from("task_a")
-    }
-
-    fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error>> {
+impl Execute for TaskA {
+    fn execute(&self, _context: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         println!("Executing Task A");
         Ok(())
     }
-
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
 }
 impl TaskA {
     pub fn other_method(&self) {
@@ -0,0 +1,3 @@
+pub mod word_count;
+
+plugin_manager::declare_plugins!(word_count::WordCount);
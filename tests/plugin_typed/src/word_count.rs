@@ -0,0 +1,14 @@
+use plugin_manager::{Plugin, TypedPlugin};
+
+#[derive(Debug, Plugin)]
+#[plugin(name = "word_count", version = "0.1.0", typed)]
+pub struct WordCount;
+
+impl TypedPlugin for WordCount {
+    type Context = String;
+    type Output = usize;
+
+    fn run(&self, context: &String) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(context.split_whitespace().count())
+    }
+}